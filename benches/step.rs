@@ -0,0 +1,76 @@
+//! Compare the sequential and the parallel step of the board, and the tiles, on a random soup
+//! The summed-area tables of the board are timed on Bosco's rule, a Larger than Life rule of range 5.
+//! Run with `cargo bench`, the number of threads can be set with `RAYON_NUM_THREADS`.
+use std::time::{Duration, Instant};
+
+use game_of_life::board::Board;
+use game_of_life::board::cell::{Cell, STATUS};
+use game_of_life::board::rule::Rule;
+use game_of_life::engine::LifeEngine;
+use game_of_life::tiles::TileBoard;
+
+const SIZE: i32 = 512;
+const GENERATIONS: u32 = 100;
+const BOSCO_GENERATIONS: u32 = 20;
+
+/// Fill a square with half of the cells alive, always the same ones
+fn soup(size: i32) -> Vec<Cell> {
+    let mut seed: u64 = 0x2545_F491_4F6C_DD1D;
+    let mut random = move || {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        seed
+    };
+
+    (0..size).flat_map(|y| (0..size).map(move |x| (x, y)))
+        .filter(|_| random() & 1 == 1)
+        .map(|(x, y)| Cell::new(x, y, STATUS::ALIVE))
+        .collect()
+}
+
+/// Advance the board and give the time it took
+fn run(mut board: Board, parallel: bool, generations: u32) -> (Duration, Board) {
+    let start = Instant::now();
+
+    for _ in 0..generations {
+        if parallel {
+            board.next_parallel();
+        } else {
+            board.next();
+        }
+    }
+    (start.elapsed(), board)
+}
+
+fn main() {
+    let cells = soup(SIZE);
+    println!("{}x{} soup, {} cells, {} generations, {} threads",
+             SIZE, SIZE, cells.len(), GENERATIONS, rayon::current_num_threads());
+
+    let (sequential, sequential_board) = run(Board::new(&cells, Rule::conway()), false, GENERATIONS);
+    let (parallel, parallel_board) = run(Board::new(&cells, Rule::conway()), true, GENERATIONS);
+    assert_eq!(sequential_board.get_leaving_cells(), parallel_board.get_leaving_cells(), "the parallel step gives another board");
+
+    let mut tiles = TileBoard::new(&cells, Rule::conway());
+    let start = Instant::now();
+    tiles.step_n(GENERATIONS as u64);
+    let tiled = start.elapsed();
+    let tile_cells = tiles.get_cells_in((i32::MIN, i32::MIN, i32::MAX, i32::MAX));
+    assert_eq!(sequential_board.get_leaving_cells(), &tile_cells.into_iter().collect(), "the tiles give another board");
+
+    println!("sequential: {:?} ({:?} per generation)", sequential, sequential / GENERATIONS);
+    println!("parallel:   {:?} ({:?} per generation)", parallel, parallel / GENERATIONS);
+    println!("tiles:      {:?} ({:?} per generation)", tiled, tiled / GENERATIONS);
+    println!("speedup:    {:.2}x parallel, {:.2}x tiles",
+             sequential.as_secs_f64() / parallel.as_secs_f64(), sequential.as_secs_f64() / tiled.as_secs_f64());
+
+    let bosco: Rule = "R5,C0,M1,S34..58,B34..45,NM".parse().unwrap();
+    let (sequential, sequential_board) = run(Board::new(&cells, bosco.clone()), false, BOSCO_GENERATIONS);
+    let (parallel, parallel_board) = run(Board::new(&cells, bosco), true, BOSCO_GENERATIONS);
+    assert_eq!(sequential_board.get_leaving_cells(), parallel_board.get_leaving_cells(), "the parallel step gives another board");
+
+    println!("Bosco's rule, {} generations", BOSCO_GENERATIONS);
+    println!("sequential: {:?} ({:?} per generation)", sequential, sequential / BOSCO_GENERATIONS);
+    println!("parallel:   {:?} ({:?} per generation)", parallel, parallel / BOSCO_GENERATIONS);
+}
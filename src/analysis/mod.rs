@@ -0,0 +1,261 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+use crate::board::Board;
+use crate::board::cell::Cell;
+use crate::board::history::HistoryDepth;
+use crate::engine::LifeEngine;
+
+/// Number of generations looked at before giving up on finding a period
+pub const DEFAULT_MAX_GENERATIONS: u64 = 1000;
+
+/// What kind of pattern it is, once the analysis is done
+/// <p> - Dies: every cell is dead at the generation </p>
+/// <p> - StillLife: the pattern doesn't change, period 1 </p>
+/// <p> - Oscillator: the pattern comes back at the same place </p>
+/// <p> - Spaceship: the pattern comes back somewhere else </p>
+/// <p> - Unknown: no repetition was found, the pattern can be growing or chaotic </p>
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum PatternKind {
+    Dies(u64),
+    StillLife,
+    Oscillator,
+    Spaceship,
+    Unknown,
+}
+
+/// The result of the analysis of a pattern
+/// <p> - kind: still life, oscillator, spaceship... </p>
+/// <p> - start: the generation of the first repeated state </p>
+/// <p> - period: the number of generations before the pattern comes back </p>
+/// <p> - displacement: how far the pattern moves in a period, as (dx, dy) </p>
+/// <p> - heat: the average number of cells changing state per generation over a period </p>
+/// <p> - volatility: the part of the cells alive during a period that don't stay alive, oscillators only </p>
+#[derive(Clone, PartialEq, Debug)]
+pub struct Analysis {
+    pub kind: PatternKind,
+    pub start: u64,
+    pub period: Option<u64>,
+    pub displacement: (i32, i32),
+    pub heat: Option<f64>,
+    pub volatility: Option<f64>,
+}
+
+/// The cells of a generation moved to the same corner, as their sorted (x, y, state)
+type Positions = Vec<(i32, i32, u8)>;
+
+/// A generation already seen, with the top left corner of its cells
+type Seen = (u64, (i32, i32));
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+impl Analysis {
+    /// Get the speed of a spaceship in the `c/4` notation
+    /// <p> - orthogonal: `c/2`, `2c/5` </p>
+    /// <p> - diagonal: `c/4 diagonal` </p>
+    /// <p> - oblique: `(2,1)c/6` </p>
+    pub fn get_speed(&self) -> Option<String> {
+        let period = self.period?;
+        let (dx, dy) = (self.displacement.0.unsigned_abs() as u64, self.displacement.1.unsigned_abs() as u64);
+        if dx == 0 && dy == 0 {
+            return None;
+        }
+        let speed = |distance: u64| {
+            let divisor = gcd(distance, period);
+            match distance / divisor {
+                1 => format!("c/{}", period / divisor),
+                distance => format!("{}c/{}", distance, period / divisor),
+            }
+        };
+
+        Some(if dx == 0 || dy == 0 {
+            speed(dx.max(dy))
+        } else if dx == dy {
+            format!("{} diagonal", speed(dx))
+        } else {
+            format!("({},{})c/{}", dx.max(dy), dx.min(dy), period)
+        })
+    }
+}
+
+/// Get the sorted positions of the cells moved to put the top left corner of their bounding box on (0, 0), and that corner
+/// The state of the cells is kept too, for the dying cells of the Generations rules.
+fn normalize(cells: &HashSet<Cell>) -> (Positions, (i32, i32)) {
+    let corner = cells.iter().fold((i32::MAX, i32::MAX), |(x, y), cell| (x.min(cell.x), y.min(cell.y)));
+    let mut positions: Positions = cells.iter()
+        .map(|cell| (cell.x - corner.0, cell.y - corner.1, cell.status.get_state()))
+        .collect();
+
+    positions.sort_unstable();
+    (positions, corner)
+}
+
+fn hash(positions: &[(i32, i32, u8)]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+
+    positions.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Is the generation `start` the same state as the current one, restored from the history of the board
+/// The board comes back to its generation after.
+fn is_repeated(board: &mut Board, start: u64, positions: &[(i32, i32, u8)]) -> bool {
+    let generation = board.get_generation();
+    let repeated = board.goto(start) && normalize(board.get_leaving_cells()).0 == positions;
+
+    board.goto(generation);
+    repeated
+}
+
+/// Get the part of the cells alive during a period that don't stay alive all along
+fn volatility(board: &mut Board, period: u64) -> f64 {
+    let mut ever_alive = board.get_leaving_cells().clone();
+    let mut always_alive = board.get_leaving_cells().clone();
+
+    for _ in 0..period {
+        board.next();
+        ever_alive.extend(board.get_leaving_cells().iter().copied());
+        always_alive.retain(|cell| board.get_leaving_cells().contains(cell));
+    }
+    (ever_alive.len() - always_alive.len()) as f64 / ever_alive.len() as f64
+}
+
+/// Run an engine on a copy of its board until a state comes back, for at most `max_generations`
+/// The copy keeps the generation and the seed, so a stochastic rule draws the same cells as the run.
+/// The states are indexed by the hash of their cells moved to the same corner, so a spaceship is found
+/// like an oscillator, with a displacement. The cells of a state with the same hash are restored from the history
+/// of the board and compared, before the period is accepted.
+pub fn analyse(engine: &dyn LifeEngine, max_generations: u64) -> Analysis {
+    let mut board = engine.to_board();
+    board.set_history_depth(HistoryDepth::Unlimited);
+    let first = board.get_generation();
+    let mut seen: HashMap<u64, Vec<Seen>> = HashMap::new();
+    // heat[g] is the number of cells that changed state to get to the generation first + g
+    let mut heat = vec![0];
+    let mut analysis = Analysis { kind: PatternKind::Unknown, start: first, period: None, displacement: (0, 0), heat: None, volatility: None };
+
+    for generation in first..=first.saturating_add(max_generations) {
+        if board.get_leaving_cells().is_empty() {
+            analysis.kind = PatternKind::Dies(generation);
+            return analysis;
+        }
+        let (positions, corner) = normalize(board.get_leaving_cells());
+        let hash = hash(&positions);
+        let repeated = seen.get(&hash).and_then(|states| states.iter()
+            .find(|&&(start, _)| is_repeated(&mut board, start, &positions))
+            .copied());
+        if let Some((start, first_corner)) = repeated {
+            let period = generation - start;
+            let changes: u64 = heat[((start - first) as usize + 1)..].iter().sum();
+
+            analysis.start = start;
+            analysis.period = Some(period);
+            analysis.displacement = (corner.0 - first_corner.0, corner.1 - first_corner.1);
+            analysis.heat = Some(changes as f64 / period as f64);
+            analysis.kind = match (period, analysis.displacement) {
+                (1, (0, 0)) => PatternKind::StillLife,
+                (_, (0, 0)) => PatternKind::Oscillator,
+                _ => PatternKind::Spaceship,
+            };
+            if analysis.displacement == (0, 0) {
+                analysis.volatility = Some(volatility(&mut board, period));
+            }
+            return analysis;
+        }
+        seen.entry(hash).or_default().push((generation, corner));
+
+        let previous = board.get_leaving_cells().clone();
+        board.next();
+        heat.push(previous.symmetric_difference(board.get_leaving_cells()).count() as u64);
+    }
+    analysis
+}
+
+impl fmt::Display for Analysis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            PatternKind::Dies(generation) => return write!(f, "Dies at generation {}", generation),
+            PatternKind::Unknown => return write!(f, "No period found"),
+            PatternKind::StillLife => write!(f, "Still life")?,
+            PatternKind::Oscillator => write!(f, "Oscillator")?,
+            PatternKind::Spaceship => write!(f, "Spaceship")?,
+        }
+        if self.start > 0 {
+            write!(f, " from generation {}", self.start)?;
+        }
+        if let Some(period) = self.period {
+            write!(f, "\nPeriod: {}", period)?;
+        }
+        if let Some(speed) = self.get_speed() {
+            write!(f, "\nDisplacement: ({}, {})\nSpeed: {}", self.displacement.0, self.displacement.1, speed)?;
+        }
+        if let Some(heat) = self.heat {
+            write!(f, "\nHeat: {:.2}", heat)?;
+        }
+        if let Some(volatility) = self.volatility {
+            write!(f, "\nVolatility: {:.2}", volatility)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::cell::fixtures;
+    use crate::board::rule::Rule;
+    use crate::pattern::PatternMetadata;
+
+    fn board(positions: &[(i32, i32)]) -> Board {
+        fixtures::board(positions, Rule::conway())
+    }
+
+    #[test]
+    fn still_life() {
+        let analysis = analyse(&board(&[(0, 0), (1, 0), (0, 1), (1, 1)]), 10);
+
+        assert_eq!(analysis.kind, PatternKind::StillLife);
+        assert_eq!((analysis.start, analysis.period), (0, Some(1)));
+    }
+
+    #[test]
+    fn oscillator() {
+        let analysis = analyse(&board(&[(0, 1), (1, 1), (2, 1)]), 10);
+
+        assert_eq!(analysis.kind, PatternKind::Oscillator);
+        assert_eq!(analysis.period, Some(2));
+        assert_eq!(analysis.heat, Some(4.0));
+        assert_eq!(analysis.volatility, Some(0.8));
+    }
+
+    #[test]
+    fn spaceship() {
+        let analysis = analyse(&board(&[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]), 10);
+
+        assert_eq!(analysis.kind, PatternKind::Spaceship);
+        assert_eq!((analysis.period, analysis.displacement), (Some(4), (1, 1)));
+        assert_eq!(analysis.get_speed().as_deref(), Some("c/4 diagonal"));
+    }
+
+    #[test]
+    fn dies() {
+        assert_eq!(analyse(&board(&[(0, 0), (1, 0)]), 10).kind, PatternKind::Dies(1));
+    }
+
+    #[test]
+    fn keeps_the_generation_and_the_history() {
+        let metadata = PatternMetadata { generation: 100, ..PatternMetadata::default() };
+        let mut board = board(&[(0, 1), (1, 1), (2, 1)]).with_metadata(metadata);
+        board.next();
+        let analysis = analyse(&board, 10);
+
+        assert_eq!((analysis.kind, analysis.start, analysis.period), (PatternKind::Oscillator, 101, Some(2)));
+        assert_eq!(board.get_generation(), 101);
+        board.prev();
+        assert_eq!(board.get_generation(), 100);
+    }
+}
@@ -1,15 +1,39 @@
 use crate::board::cell::STATUS::ALIVE;
+use crate::board::rule::Rule;
 
-/// Define a cell alive or dead
+/// Define a cell alive, dead, or dying with the Generations rules
+/// <p> - DEAD: the state 0 </p>
+/// <p> - ALIVE: the state 1, the only one counted as a living neighbour </p>
+/// <p> - DYING(n): the state n, from 2 to the number of states of the rule - 1,
+///  a dying cell can't be born again before it is dead </p>
 /// This should not be access by something else than the cell
 /// [TODO] Remove the pub
 #[derive(Clone, Copy, Eq, PartialEq, Debug, Hash)]
 pub enum STATUS {
-    DEAD = 0,
-    ALIVE = 1
+    DEAD,
+    ALIVE,
+    DYING(u8),
 }
 
 impl STATUS {
+    /// Get the status of a state number, 0 is dead and 1 alive
+    pub fn from_state(state: u8) -> Self {
+        match state {
+            0 => STATUS::DEAD,
+            1 => STATUS::ALIVE,
+            state => STATUS::DYING(state),
+        }
+    }
+
+    /// Get the state number, 0 is dead and 1 alive
+    pub fn get_state(self) -> u8 {
+        match self {
+            STATUS::DEAD => 0,
+            STATUS::ALIVE => 1,
+            STATUS::DYING(state) => state,
+        }
+    }
+
     pub fn get_char(self) -> char {
         if STATUS::DEAD == self {
             '.'
@@ -18,32 +42,42 @@ impl STATUS {
         }
     }
 
-    pub fn get_from_char(s: char) -> STATUS {
+    /// Get the status of a plaintext grid character, None when the character has no meaning
+    /// The `.cells` `.`/`O` grids, the `0`/`1` grids and the `.`/`*` grids are understood
+    pub fn get_from_char(s: char) -> Option<STATUS> {
         match s {
-            '.' => STATUS::DEAD,
-            'O' => STATUS::ALIVE,
-            _other => panic!("Wrong format")
+            '.' | '0' => Some(STATUS::DEAD),
+            'O' | '1' | '*' => Some(STATUS::ALIVE),
+            _other => None
         }
     }
 
+    /// Get the opposite status, a dying cell is killed
     pub fn inverse(self) -> Self {
-        if STATUS::ALIVE == self {
-            STATUS::DEAD
-        } else if STATUS::DEAD == self {
-            STATUS::ALIVE
-        } else {
-            self
+        match self {
+            STATUS::DEAD => STATUS::ALIVE,
+            STATUS::ALIVE | STATUS::DYING(_) => STATUS::DEAD,
         }
     }
 
+    /// Get the status of the next state of a rule with `states` states, after the last one comes the dead state
+    /// For the two states rules, it is the opposite status.
+    pub fn cycle(self, states: u8) -> Self {
+        STATUS::from_state((self.get_state() + 1) % states.max(2))
+    }
+
     pub fn is_alive(self) -> bool {
         self == ALIVE
     }
+
+    pub fn is_dead(self) -> bool {
+        self == STATUS::DEAD
+    }
 }
 
 /// Define a cell of the board
 /// It has a position on it and a status
-#[derive(Clone, Copy, Eq, PartialEq, Hash)]
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Hash)]
 pub struct Cell {
     pub x: i32,
     pub y: i32,
@@ -60,16 +94,48 @@ impl Cell {
         self.status.is_alive()
     }
 
-    /// Apply the game of life rules on this cell
-    pub fn apply_rules(&self, adj_live_cells: usize) -> Cell {
-        if self.is_alive() && adj_live_cells > 3 || adj_live_cells < 2 {
-            Cell::new(self.x, self.y, STATUS::DEAD)
-        } else if self.is_alive() {
-            Cell::new(self.x, self.y, STATUS::ALIVE)
-        } else if !self.is_alive() && adj_live_cells == 3 {
-            Cell::new(self.x, self.y, STATUS::ALIVE)
-        } else {
-            Cell::new(self.x, self.y, STATUS::DEAD)
+    /// Apply the rule of the board on this cell, from its living neighbours as bits
+    pub fn apply_rules(&self, neighbourhood: u8, rule: &Rule) -> Cell {
+        Cell::new(self.x, self.y, rule.next_status(self.status, neighbourhood))
+    }
+}
+/// The cells and the comparisons shared by the tests
+#[cfg(test)]
+pub(crate) mod fixtures {
+    use super::{Cell, STATUS};
+    use crate::board::Board;
+    use crate::board::rule::Rule;
+    use crate::engine::LifeEngine;
+
+    /// Get living cells at these positions
+    pub(crate) fn alive(positions: &[(i32, i32)]) -> Vec<Cell> {
+        positions.iter().map(|&(x, y)| Cell::new(x, y, STATUS::ALIVE)).collect()
+    }
+
+    /// Get a board with living cells at these positions
+    pub(crate) fn board(positions: &[(i32, i32)], rule: Rule) -> Board {
+        Board::new(&alive(positions), rule)
+    }
+
+    /// Get the sorted positions of some cells
+    pub(crate) fn sorted<'a, I: IntoIterator<Item = &'a Cell>>(cells: I) -> Vec<(i32, i32)> {
+        let mut positions: Vec<_> = cells.into_iter().map(|cell| (cell.x, cell.y)).collect();
+        positions.sort_unstable();
+        positions
+    }
+
+    /// Get the positions and the states of some cells, in reading order
+    pub(crate) fn sorted_states<'a, I: IntoIterator<Item = &'a Cell>>(cells: I) -> Vec<(i32, i32, u8)> {
+        let mut cells: Vec<_> = cells.into_iter().map(|cell| (cell.x, cell.y, cell.status.get_state())).collect();
+        cells.sort_unstable_by_key(|&(x, y, _)| (y, x));
+        cells
+    }
+
+    /// Get the sorted positions of the cells of an engine
+    pub(crate) fn positions(engine: &dyn LifeEngine) -> Vec<(i32, i32)> {
+        match engine.get_bounding_box() {
+            Some(rect) => sorted(&engine.get_cells_in(rect)),
+            None => vec![],
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,293 @@
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::board::cell::Cell;
+
+/// A keyframe, a copy of all the living cells, is kept every this number of generations
+pub const KEYFRAME_INTERVAL: u64 = 1000;
+
+/// How many generations can be undone
+/// <p> - Limited: only the last ones, the oldest are forgotten </p>
+/// <p> - Unlimited: back to the start, the memory grows with the changes of the pattern </p>
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum HistoryDepth {
+    Limited(usize),
+    Unlimited,
+}
+
+impl HistoryDepth {
+    /// Can this number of generations be kept
+    pub fn allows(self, len: usize) -> bool {
+        match self {
+            HistoryDepth::Limited(depth) => len <= depth,
+            HistoryDepth::Unlimited => true,
+        }
+    }
+}
+
+impl Default for HistoryDepth {
+    fn default() -> Self {
+        HistoryDepth::Limited(10)
+    }
+}
+
+/// Read a number of generations, or `unlimited`
+impl FromStr for HistoryDepth {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "unlimited" => Ok(HistoryDepth::Unlimited),
+            depth => depth.parse().map(HistoryDepth::Limited)
+                .map_err(|_| format!("Invalid history depth '{}', expected a number or unlimited", depth)),
+        }
+    }
+}
+
+impl fmt::Display for HistoryDepth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HistoryDepth::Limited(depth) => write!(f, "{}", depth),
+            HistoryDepth::Unlimited => write!(f, "unlimited"),
+        }
+    }
+}
+
+/// The cells born and dead between two generations
+#[derive(Clone, Default, Debug)]
+struct Diff {
+    births: Vec<Cell>,
+    deaths: Vec<Cell>,
+}
+
+/// The past generations of a board, stored as the changes from one generation to the next.
+/// A keyframe is kept every `KEYFRAME_INTERVAL` generations,
+/// so a far generation is restored without undoing all the changes since it.
+#[derive(Clone)]
+pub struct History {
+    depth: HistoryDepth,
+    /// The last diff leads to the generation `generation`
+    diffs: VecDeque<Diff>,
+    generation: u64,
+    keyframes: BTreeMap<u64, HashSet<Cell>>,
+}
+
+impl History {
+    pub fn new(depth: HistoryDepth) -> Self {
+        History { depth, diffs: VecDeque::new(), generation: 0, keyframes: BTreeMap::new() }
+    }
+
+    #[inline]
+    pub fn get_depth(&self) -> HistoryDepth {
+        self.depth
+    }
+
+    /// Change the depth, the generations beyond it are forgotten
+    pub fn set_depth(&mut self, depth: HistoryDepth) {
+        self.depth = depth;
+        self.trim();
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.diffs.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.diffs.is_empty()
+    }
+
+    /// Get the oldest generation that can be restored
+    #[inline]
+    pub fn get_oldest_generation(&self) -> u64 {
+        self.generation - self.diffs.len() as u64
+    }
+
+    /// Record the change from the cells `previous` of the generation `generation` to the cells `next`
+    pub fn push(&mut self, generation: u64, previous: &HashSet<Cell>, next: &HashSet<Cell>) {
+        if self.generation != generation {
+            self.clear();
+        }
+        if generation.is_multiple_of(KEYFRAME_INTERVAL) {
+            self.keyframes.insert(generation, previous.clone());
+        }
+        self.diffs.push_back(Diff {
+            births: next.difference(previous).copied().collect(),
+            deaths: previous.difference(next).copied().collect(),
+        });
+        self.generation = generation + 1;
+        self.trim();
+    }
+
+    /// Undo the last change on the cells, false when there is nothing to undo
+    pub fn pop(&mut self, cells: &mut HashSet<Cell>) -> bool {
+        let diff = match self.diffs.pop_back() {
+            Some(diff) => diff,
+            None => return false,
+        };
+
+        diff.births.iter().for_each(|cell| { cells.remove(cell); });
+        cells.extend(diff.deaths);
+        self.generation -= 1;
+        // The next generations can change, after an edit of a cell
+        self.keyframes.split_off(&(self.generation + 1));
+        true
+    }
+
+    /// Restore the cells of an older generation, from the nearest keyframe after it or the current cells
+    /// False when the generation is not in the history anymore.
+    pub fn restore(&mut self, generation: u64, cells: &mut HashSet<Cell>) -> bool {
+        if generation < self.get_oldest_generation() || generation > self.generation {
+            return false;
+        }
+        if let Some((&keyframe, keyframe_cells)) = self.keyframes.range(generation..).next() {
+            *cells = keyframe_cells.clone();
+            self.diffs.truncate(self.diffs.len() - (self.generation - keyframe) as usize);
+            self.generation = keyframe;
+            self.keyframes.split_off(&(keyframe + 1));
+        }
+        while self.generation > generation {
+            self.pop(cells);
+        }
+        true
+    }
+
+    /// Keep the last change right after a cell of the current generation is set by hand,
+    /// so undoing it still gives back the previous generation
+    /// `was_alive` is true when the cell, with its status, is removed from the board.
+    pub fn record_edit(&mut self, cell: Cell, was_alive: bool) {
+        if let Some(diff) = self.diffs.back_mut() {
+            // A cell killed by hand was born in the last change, or dies in it now
+            let (undone, done) = if was_alive {
+                (&mut diff.births, &mut diff.deaths)
+            } else {
+                (&mut diff.deaths, &mut diff.births)
+            };
+            match undone.iter().position(|&c| c == cell) {
+                Some(index) => { undone.swap_remove(index); }
+                None => done.push(cell),
+            }
+        }
+        if let Some(keyframe) = self.keyframes.get_mut(&self.generation) {
+            if was_alive {
+                keyframe.remove(&cell);
+            } else {
+                keyframe.insert(cell);
+            }
+        }
+    }
+
+    fn trim(&mut self) {
+        while !self.depth.allows(self.diffs.len()) {
+            self.diffs.pop_front();
+        }
+        self.keyframes = self.keyframes.split_off(&self.get_oldest_generation());
+    }
+
+    /// Forget all the generations, after the cells were replaced
+    pub fn clear(&mut self) {
+        self.diffs.clear();
+        self.keyframes.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::cell::STATUS;
+
+    /// The cells of the generation g, one living cell at (g, 0)
+    fn cells(generation: u64) -> HashSet<Cell> {
+        std::iter::once(Cell::new(generation as i32, 0, STATUS::ALIVE)).collect()
+    }
+
+    /// Get a history of the generations 0 to `last`
+    fn history(depth: HistoryDepth, last: u64) -> History {
+        let mut history = History::new(depth);
+        (0..last).for_each(|generation| history.push(generation, &cells(generation), &cells(generation + 1)));
+        history
+    }
+
+    #[test]
+    fn pop_and_restore_across_keyframes() {
+        let last = 2 * KEYFRAME_INTERVAL + 10;
+        let mut history = history(HistoryDepth::Unlimited, last);
+        let mut current = cells(last);
+
+        assert!(history.pop(&mut current));
+        assert_eq!(current, cells(last - 1));
+        assert!(history.restore(KEYFRAME_INTERVAL + 3, &mut current));
+        assert_eq!(current, cells(KEYFRAME_INTERVAL + 3));
+        assert!(history.restore(KEYFRAME_INTERVAL - 1, &mut current));
+        assert_eq!(current, cells(KEYFRAME_INTERVAL - 1));
+        assert!(history.restore(0, &mut current));
+        assert_eq!(current, cells(0));
+        assert!(!history.pop(&mut current));
+    }
+
+    #[test]
+    fn restore_after_the_history_is_refused() {
+        let mut history = history(HistoryDepth::Unlimited, 5);
+        let mut current = cells(5);
+
+        assert!(!history.restore(6, &mut current));
+        assert_eq!(current, cells(5));
+    }
+
+    #[test]
+    fn trim_drops_the_oldest_generations() {
+        let last = KEYFRAME_INTERVAL + 5;
+        let mut history = history(HistoryDepth::Limited(10), last);
+        let mut current = cells(last);
+
+        assert_eq!(history.len(), 10);
+        assert_eq!(history.get_oldest_generation(), last - 10);
+        assert!(!history.restore(last - 11, &mut current));
+        assert!(history.restore(last - 10, &mut current));
+        assert_eq!(current, cells(last - 10));
+    }
+
+    #[test]
+    fn smaller_depth_keeps_the_last_generations() {
+        let mut history = history(HistoryDepth::Limited(10), 20);
+        let mut current = cells(20);
+        history.set_depth(HistoryDepth::Limited(3));
+
+        assert_eq!((history.len(), history.get_oldest_generation()), (3, 17));
+        assert!(history.restore(17, &mut current));
+        assert_eq!(current, cells(17));
+    }
+
+    #[test]
+    fn pop_undoes_an_edit() {
+        let mut history = history(HistoryDepth::Unlimited, 3);
+        let mut current = cells(3);
+        let added = Cell::new(10, 10, STATUS::ALIVE);
+        let removed = Cell::new(3, 0, STATUS::ALIVE);
+        current.insert(added);
+        history.record_edit(added, false);
+        current.remove(&removed);
+        history.record_edit(removed, true);
+
+        assert!(history.pop(&mut current));
+        assert_eq!(current, cells(2));
+    }
+
+    #[test]
+    fn edit_of_a_keyframe_generation() {
+        let mut history = history(HistoryDepth::Unlimited, KEYFRAME_INTERVAL + 1);
+        let mut current = cells(KEYFRAME_INTERVAL + 1);
+        history.pop(&mut current);
+        let added = Cell::new(-5, 5, STATUS::ALIVE);
+        current.insert(added);
+        history.record_edit(added, false);
+        let mut edited = current.clone();
+
+        assert!(history.restore(KEYFRAME_INTERVAL, &mut edited));
+        assert_eq!(edited, current);
+        assert!(history.restore(KEYFRAME_INTERVAL - 1, &mut edited));
+        assert_eq!(edited, cells(KEYFRAME_INTERVAL - 1));
+    }
+}
@@ -0,0 +1,290 @@
+use std::collections::HashSet;
+use std::convert::TryFrom;
+
+use rayon::prelude::*;
+
+use crate::board::cell::{Cell, STATUS};
+use crate::board::rule::Rule;
+
+/// The largest range of a Larger than Life rule, like in Golly
+pub const MAX_RANGE: u32 = 500;
+
+/// Side of the squares the neighbours are counted by, it grows to the range of the rule
+const TILE_SIZE: i32 = 64;
+
+/// The cells counted up to the range of a Larger than Life rule
+/// <p> - Moore `NM`: the square around the cell </p>
+/// <p> - VonNeumann `NN`: the diamond, where |dx| + |dy| <= range </p>
+/// <p> - Circular `NC`: the disc, where dx² + dy² <= range² + range </p>
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Hash)]
+pub enum Shape {
+    Moore,
+    VonNeumann,
+    Circular,
+}
+
+impl Shape {
+    /// Get how far the shape goes left and right on the row `dy` away from the cell
+    fn half_width(&self, range: i32, dy: i32) -> i32 {
+        match *self {
+            Shape::Moore => range,
+            Shape::VonNeumann => range - dy.abs(),
+            Shape::Circular => ((range * range + range - dy * dy) as f64).sqrt() as i32,
+        }
+    }
+}
+
+/// Define a Larger than Life rule, written like Golly `R5,C0,M1,S34..58,B34..45,NM` for Bosco's rule
+/// <p> - range: how far the neighbours are, from 1 to `MAX_RANGE` </p>
+/// <p> - middle: `M1` when a living cell counts itself </p>
+/// <p> - survival: the counts, from min to max, where a living cell stays alive </p>
+/// <p> - birth: the counts where a dead cell becomes alive, from 1 </p>
+/// <p> - shape: the cells counted up to the range </p>
+/// The number of states `C` and the topology are kept by the rule, like for the other rules.
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Hash)]
+pub struct LargerThanLife {
+    range: u32,
+    middle: bool,
+    survival: (u32, u32),
+    birth: (u32, u32),
+    shape: Shape,
+}
+
+impl LargerThanLife {
+    #[inline]
+    pub fn get_range(&self) -> u32 {
+        self.range
+    }
+
+    /// Get the number of neighbours, without the cell itself
+    pub fn get_size(&self) -> u32 {
+        let range = self.range as i32;
+
+        (-range..=range).map(|dy| 2 * self.shape.half_width(range, dy) as u32 + 1).sum::<u32>() - 1
+    }
+
+    /// Give the next state of a cell from its state and its number of living neighbours
+    #[inline]
+    pub fn apply(&self, alive: bool, count: u32) -> bool {
+        if alive {
+            let count = count + self.middle as u32;
+            count >= self.survival.0 && count <= self.survival.1
+        } else {
+            count >= self.birth.0 && count <= self.birth.1
+        }
+    }
+
+    /// Parse the Golly notation, without the topology, into the rule and its number of states
+    /// `C0`, `C1` and `C2` are the two states rules, the others have dying states like the Generations rules.
+    pub fn parse(s: &str) -> Result<(Self, u8), String> {
+        let invalid = || format!("Invalid Larger than Life rule '{}', expected like R5,C0,M1,S34..58,B34..45,NM", s);
+        let (mut range, mut states, mut middle, mut survival, mut birth, mut shape) = (None, 2, false, None, None, Shape::Moore);
+
+        for part in s.split(',').map(str::trim) {
+            let value = part.get(1..).ok_or_else(invalid)?;
+            let number = || value.parse::<u32>().map_err(|_| invalid());
+            let counts = || match value.split_once("..") {
+                Some((min, max)) => match (min.parse::<u32>(), max.parse::<u32>()) {
+                    (Ok(min), Ok(max)) if min <= max => Ok((min, max)),
+                    _ => Err(invalid()),
+                },
+                None => number().map(|count| (count, count)),
+            };
+
+            match part.chars().next().map(|c| c.to_ascii_uppercase()) {
+                Some('R') => range = Some(number()?),
+                Some('C') => states = u8::try_from(number()?).map_err(|_| invalid())?.max(2),
+                Some('M') => middle = number()? != 0,
+                Some('S') => survival = Some(counts()?),
+                Some('B') => birth = Some(counts()?),
+                Some('N') => shape = match value.to_ascii_uppercase().as_str() {
+                    "M" => Shape::Moore,
+                    "N" => Shape::VonNeumann,
+                    "C" => Shape::Circular,
+                    _ => return Err(invalid()),
+                },
+                _ => return Err(invalid()),
+            }
+        }
+
+        let ltl = match (range, survival, birth) {
+            (Some(range), Some(survival), Some(birth)) => LargerThanLife { range, middle, survival, birth, shape },
+            _ => return Err(invalid()),
+        };
+        if ltl.range == 0 || ltl.range > MAX_RANGE {
+            return Err(format!("Invalid range '{}' in rule, expected 1 to {}", ltl.range, MAX_RANGE));
+        }
+        if ltl.birth.0 == 0 {
+            return Err(String::from("Invalid birth 'B0' in rule, a Larger than Life birth needs a living neighbour"));
+        }
+        if ltl.survival.1 > ltl.get_size() + ltl.middle as u32 || ltl.birth.1 > ltl.get_size() {
+            return Err(format!("Invalid count in rule '{}', there are {} neighbours", s, ltl.get_size()));
+        }
+        Ok((ltl, states))
+    }
+
+    /// Write the rule in the Golly notation, with its number of states
+    pub fn notation(&self, states: u8) -> String {
+        let shape = match self.shape {
+            Shape::Moore => 'M',
+            Shape::VonNeumann => 'N',
+            Shape::Circular => 'C',
+        };
+
+        format!("R{},C{},M{},S{}..{},B{}..{},N{}", self.range, if states > 2 { states } else { 0 }, self.middle as u8,
+                self.survival.0, self.survival.1, self.birth.0, self.birth.1, shape)
+    }
+}
+
+/// Get the next cells of a square of the board, the square (x, y) starts at the cell (x * size, y * size)
+/// The living cells of the square and of its border of `range` cells are summed in a table,
+/// where `sums[y][x]` is the number of living cells above and left of (x, y),
+/// so a row of neighbours is counted in 4 reads whatever the range.
+fn next_square(cells: &HashSet<Cell>, dying: &HashSet<(i32, i32)>, rule: &Rule, ltl: &LargerThanLife,
+               size: i32, (square_x, square_y): (i32, i32)) -> Vec<Cell> {
+    let range = ltl.range as i32;
+    let topology = rule.get_topology();
+    let (left, top) = (square_x * size - range, square_y * size - range);
+    let side = (size + 2 * range) as usize;
+    let width = side + 1;
+    let mut sums = vec![0u32; width * width];
+
+    for y in 0..side {
+        let mut row = 0;
+        for x in 0..side {
+            let alive = topology.wrap(left + x as i32, top + y as i32)
+                .is_some_and(|(x, y)| cells.contains(&Cell::new(x, y, STATUS::ALIVE)));
+            row += alive as u32;
+            sums[(y + 1) * width + x + 1] = sums[y * width + x + 1] + row;
+        }
+    }
+    // The living cells in the rectangle from (min_x, min_y) to (max_x, max_y) included
+    let count = |min_x: i32, min_y: i32, max_x: i32, max_y: i32| {
+        let (min_x, min_y, max_x, max_y) = (min_x as usize, min_y as usize, max_x as usize + 1, max_y as usize + 1);
+        sums[max_y * width + max_x] + sums[min_y * width + min_x] - sums[min_y * width + max_x] - sums[max_y * width + min_x]
+    };
+    let half_widths: Vec<(i32, i32)> = (-range..=range).map(|dy| (dy, ltl.shape.half_width(range, dy))).collect();
+
+    let mut res = vec![];
+    for y in range..range + size {
+        for x in range..range + size {
+            let pos = match topology.wrap(left + x, top + y) {
+                Some(pos) => pos,
+                None => continue,
+            };
+            let alive = count(x, y, x, y) == 1;
+            if !alive && dying.contains(&pos) {
+                continue;
+            }
+            let neighbours = match ltl.shape {
+                Shape::Moore => count(x - range, y - range, x + range, y + range),
+                _ => half_widths.iter().map(|&(dy, half)| count(x - half, y + dy, x + half, y + dy)).sum(),
+            } - alive as u32;
+            let status = rule.next_status_from(if alive { STATUS::ALIVE } else { STATUS::DEAD }, ltl.apply(alive, neighbours));
+            if !status.is_dead() {
+                res.push(Cell::new(pos.0, pos.1, status));
+            }
+        }
+    }
+    res
+}
+
+/// Compute the next generation of a board with a Larger than Life rule
+/// The board is cut in squares of `TILE_SIZE` cells, or of the range when it is larger,
+/// and the squares of the living cells and around them are computed from summed-area tables,
+/// on all the threads when `parallel`. A cell near a joined edge can be computed twice, with the same state.
+pub(crate) fn next(cells: &HashSet<Cell>, rule: &Rule, ltl: &LargerThanLife, parallel: bool) -> HashSet<Cell> {
+    let size = TILE_SIZE.max(ltl.range as i32);
+    let squares: HashSet<(i32, i32)> = cells.iter()
+        .filter(|cell| cell.is_alive())
+        .map(|cell| (cell.x.div_euclid(size), cell.y.div_euclid(size)))
+        .flat_map(|(x, y)| (-1..=1).flat_map(move |dy| (-1..=1).map(move |dx| (x + dx, y + dy))))
+        .collect();
+    let dying: HashSet<(i32, i32)> = cells.iter().filter(|cell| !cell.is_alive()).map(|cell| (cell.x, cell.y)).collect();
+    let square = |&square: &(i32, i32)| next_square(cells, &dying, rule, ltl, size, square);
+
+    let mut res: HashSet<Cell> = if parallel {
+        squares.par_iter().flat_map_iter(square).collect()
+    } else {
+        squares.iter().flat_map(square).collect()
+    };
+    // The dying cells don't depend on their neighbours
+    res.extend(cells.iter()
+        .filter(|cell| !cell.is_alive())
+        .map(|cell| Cell::new(cell.x, cell.y, rule.next_status_from(cell.status, false)))
+        .filter(|cell| !cell.status.is_dead()));
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A soup of living cells in the rectangle from (0, 0) to (size - 1, size - 1)
+    fn soup(size: i32) -> HashSet<Cell> {
+        let mut state: u32 = 2024;
+        (0..size).flat_map(|y| (0..size).map(move |x| (x, y)))
+            .filter(|_| {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                (state >> 16) & 1 == 0
+            })
+            .map(|(x, y)| Cell::new(x, y, STATUS::ALIVE))
+            .collect()
+    }
+
+    /// Compute the next cells by counting the neighbours of every cell one by one
+    fn brute_force(cells: &HashSet<Cell>, rule: &Rule) -> HashSet<Cell> {
+        let ltl = rule.get_larger_than_life().unwrap();
+        let range = ltl.range as i32;
+        let topology = rule.get_topology();
+        let alive = |x: i32, y: i32| topology.wrap(x, y).is_some_and(|(x, y)| cells.contains(&Cell::new(x, y, STATUS::ALIVE)));
+        let positions: HashSet<(i32, i32)> = cells.iter()
+            .flat_map(|cell| (-range..=range).flat_map(move |dy| (-range..=range).map(move |dx| (cell.x + dx, cell.y + dy))))
+            .filter_map(|(x, y)| topology.wrap(x, y))
+            .collect();
+
+        positions.into_iter()
+            .filter(|&(x, y)| {
+                let neighbours = (-range..=range)
+                    .flat_map(|dy| {
+                        let half = ltl.shape.half_width(range, dy);
+                        (-half..=half).map(move |dx| (dx, dy))
+                    })
+                    .filter(|&(dx, dy)| (dx, dy) != (0, 0) && alive(x + dx, y + dy))
+                    .count() as u32;
+                ltl.apply(alive(x, y), neighbours)
+            })
+            .map(|(x, y)| Cell::new(x, y, STATUS::ALIVE))
+            .collect()
+    }
+
+    fn check_against_brute_force(rule: &str, size: i32) {
+        let rule: Rule = rule.parse().unwrap();
+        let ltl = rule.get_larger_than_life().unwrap();
+        let mut cells = soup(size);
+
+        for generation in 1..=3 {
+            let expected = brute_force(&cells, &rule);
+            assert!(!expected.is_empty(), "generation {}", generation);
+            let res = next(&cells, &rule, ltl, false);
+            assert_eq!(res, expected, "generation {}", generation);
+            assert_eq!(next(&cells, &rule, ltl, true), expected);
+            cells = res;
+        }
+    }
+
+    #[test]
+    fn bosco_matches_brute_force() {
+        check_against_brute_force("R5,C0,M1,S34..58,B34..45,NM", 40);
+    }
+
+    #[test]
+    fn von_neumann_matches_brute_force() {
+        check_against_brute_force("R3,C0,M0,S6..12,B7..10,NN", 30);
+    }
+
+    #[test]
+    fn torus_matches_brute_force() {
+        check_against_brute_force("R5,C0,M1,S34..58,B34..45,NM:T30,24", 30);
+    }
+}
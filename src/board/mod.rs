@@ -1,197 +1,520 @@
 pub mod cell;
+pub mod history;
+pub mod ltl;
+pub mod neighbourhood;
+pub mod rule;
+pub mod stochastic;
+pub mod table;
+pub mod topology;
+pub mod wireworld;
 
 use cell::{Cell, STATUS};
-use std::collections::{VecDeque, HashSet};
-use std::ops::Add;
-
+use history::{History, HistoryDepth};
+use rule::Rule;
+use crate::engine::LifeEngine;
+use crate::pattern::PatternMetadata;
+use rayon::prelude::*;
+use std::collections::{BTreeMap, HashSet};
+
+/// The board is unbounded, only the living and dying cells are stored.
+/// A cell can be anywhere on the plane, negative positions included,
+/// unless the topology of the rule bounds the board.
+/// A parallel board computes each generation by row bands on all the threads.
+/// The births and survivals of a stochastic rule are drawn from the seed of the board.
+#[derive(Clone)]
 pub struct Board {
-    rows: Vec<Vec<Cell>>,
-    actual: Box<HashSet<Cell>>,
-    history: VecDeque<Box<HashSet<Cell>>>,
-    initial_state: Box<HashSet<Cell>>,
+    actual: HashSet<Cell>,
+    parallel: bool,
+    history: History,
+    initial_state: HashSet<Cell>,
+    generation: u64,
+    rule: Rule,
+    seed: u64,
+    /// A cell was set by hand, the initial cells don't lead to the current ones anymore
+    edited: bool,
+    metadata: PatternMetadata,
 }
 
-
-fn cells_to_string(cells: &Box<HashSet<Cell>>) -> String {
-    let res = cells.iter().filter(|&cell| cell.is_alive()).copied().collect::<HashSet<Cell>>();
-    let c_cells = Box::from(res);
-
-    let s_x = c_cells.iter().min_by(|&a, &b| a.x.cmp(&b.x)).unwrap();
-    let s_y = c_cells.iter().min_by(|&a, &b| a.y.cmp(&b.y)).unwrap();
-    let b_x = c_cells.iter().max_by(|&a, &b| a.x.cmp(&b.x)).unwrap();
-    let b_y = c_cells.iter().max_by(|&a, &b| a.y.cmp(&b.y)).unwrap();
-
-    (s_y.y..(b_y.y + 1)).map(|y| {
-        (s_x.x..(b_x.x + 1)).map(|x| {
-            if c_cells.contains(&Cell::new(x, y, STATUS::ALIVE)) {
-                STATUS::ALIVE.get_char()
-            } else {
-                STATUS::DEAD.get_char()
-            }
-        }).collect::<String>().add("\n")
-    }).collect()
+/// Position of the adjacent cells, relative to a cell
+/// The bit i of a neighbourhood is the cell at `ADJ_POSITIONS[i]`.
+pub(crate) const ADJ_POSITIONS: [(i32, i32); 8] = [
+    (-1, -1), (0, -1), (1, -1),
+    (-1,  0),          (1,  0),
+    (-1,  1), (0,  1), (1,  1),
+];
+
+/// Height of the row bands of a parallel step
+/// The bands don't depend on the number of threads, so neither does the result.
+const BAND_HEIGHT: i32 = 64;
+
+/// Get the smallest rectangle containing all the cells, as (min_x, min_y, max_x, max_y)
+pub(crate) fn cells_bounding_box<'a, I: IntoIterator<Item = &'a Cell>>(cells: I) -> Option<(i32, i32, i32, i32)> {
+    cells.into_iter().fold(None, |acc, cell| match acc {
+        None => Some((cell.x, cell.y, cell.x, cell.y)),
+        Some((s_x, s_y, b_x, b_y)) => Some((s_x.min(cell.x), s_y.min(cell.y), b_x.max(cell.x), b_y.max(cell.y)))
+    })
 }
 
 /// Define the board logic
 impl Board {
-    fn get_status_or_dead(x: i64, y: i64, obj_b: &Vec<Vec<char>>) -> Option<STATUS> {
-        if x <= 0 && y <= 0 {
-            None
-        } else {
-            let &c = obj_b.get(y as usize)?.get(x as usize)?;
-            Some(STATUS::get_from_char(c))
-        }
-    }
+    /// Construct the board from its living and dying cells
+    /// The cells outside a bounded board are wrapped on it, or dropped on a bounded plane.
+    /// A state the rule doesn't have is read as alive.
+    pub fn new(cells: &[Cell], rule: Rule) -> Self {
+        let actual: HashSet<Cell> = cells.iter()
+            .filter(|cell| !cell.status.is_dead())
+            .filter_map(|cell| rule.get_topology().wrap(cell.x, cell.y).map(|(x, y)| (x, y, cell.status)))
+            .map(|(x, y, status)| if status.get_state() < rule.get_states() {
+                Cell::new(x, y, status)
+            } else {
+                Cell::new(x, y, STATUS::ALIVE)
+            })
+            .collect();
 
-    fn get_cell_from_char(size: usize, obj_b: &Vec<Vec<char>>, (x, y): (i64, i64)) -> Cell {
-        let res = match Board::get_status_or_dead(
-            ((size / 2) - obj_b.len() / 2) as i64 - x,
-            ((size / 2) - obj_b.len() / 2) as i64 - y,
-            &obj_b
-        ) {
-            None => STATUS::DEAD,
-            Some(status) => status
-        };
-        Cell::new(x as i32, y as i32, res)
+        Board{actual: actual.clone(), parallel: false, initial_state: actual, history: History::new(HistoryDepth::default()), generation: 0, rule,
+              seed: 0, edited: false, metadata: PatternMetadata::default()}
     }
 
-    /// Construct the board from a map
-    pub fn new(size: usize, obj: Vec<&str>) -> Self {
-        let mut actual = Box::new(HashSet::new());
-        let obj_b: Vec<Vec<char>> = obj.iter()
-            .map(|&s| s.chars().collect::<Vec<char>>())
-            .filter(|elem| elem.get(0) == None || elem.get(0) != Some(&'!'))
-            .collect();
+    /// Attach the metadata of the loaded pattern, the board starts at its generation
+    pub fn with_metadata(self, metadata: PatternMetadata) -> Self {
+        Board { generation: metadata.generation, seed: metadata.seed.unwrap_or(self.seed), metadata, ..self }
+    }
 
-        let rows = (0..size as i64).map(|y| {
-            (0..size as i64).map(|x| {
-                let c = Self::get_cell_from_char(size, &obj_b, (x, y));
-                if c.is_alive() {
-                    actual.insert(c);
-                }
-                c
-            }).collect()
-        }).collect();
+    /// Draw the births and survivals of a stochastic rule from this seed, the same seed replays the same run
+    pub fn with_seed(self, seed: u64) -> Self {
+        Board { seed, ..self }
+    }
 
-        Board{rows, actual: actual.clone(), initial_state: actual.clone(), history: VecDeque::with_capacity(10000)}
+    /// Compute the generations by row bands on all the threads, or on this one
+    pub fn with_parallel(self, parallel: bool) -> Self {
+        Board { parallel, ..self }
     }
 
     #[inline]
-    pub fn get_size(&self) -> (usize, usize) {
-        (self.rows.len(), self.rows[0].len())
+    pub fn is_parallel(&self) -> bool {
+        self.parallel
     }
 
     #[inline]
-    pub fn get_leaving_cells(&self) -> &Box<HashSet<Cell>> {
-        &self.actual
+    pub fn get_metadata(&self) -> &PatternMetadata {
+        &self.metadata
     }
 
     #[inline]
-    pub fn board_to_string(&self) -> String {
-        cells_to_string(&self.actual)
+    pub fn get_rule(&self) -> &Rule {
+        &self.rule
     }
 
     #[inline]
-    pub fn initial_board_to_string(&self) -> String {
-        cells_to_string(&self.initial_state)
+    pub fn get_leaving_cells(&self) -> &HashSet<Cell> {
+        &self.actual
     }
 
+    /// Get the smallest rectangle containing the living cells, as (min_x, min_y, max_x, max_y)
     #[inline]
-    pub fn get_cell(&self, x: i32, y: i32) -> Option<&Cell> {
-        self.rows.get(y as usize)?.get(x as usize)
+    pub fn get_bounding_box(&self) -> Option<(i32, i32, i32, i32)> {
+        cells_bounding_box(&self.actual)
     }
 
+    /// Get the cell at this position, following the edges of the board
+    /// The dying states are looked for one by one, after the living one.
     #[inline]
-    pub fn get_cell_or_dead(&self, x: i32, y: i32) -> Cell {
-        match self.get_cell(x, y) {
-            None => Cell::new(0, 0, STATUS::DEAD),
-            Some(&cell) => cell
+    pub fn get_cell(&self, x: i32, y: i32) -> Cell {
+        match self.rule.get_topology().wrap(x, y) {
+            Some((x, y)) if self.actual.contains(&Cell::new(x, y, STATUS::ALIVE)) => Cell::new(x, y, STATUS::ALIVE),
+            Some((x, y)) => (2..self.rule.get_states())
+                .map(|state| Cell::new(x, y, STATUS::DYING(state)))
+                .find(|cell| self.actual.contains(cell))
+                .unwrap_or_else(|| Cell::new(x, y, STATUS::DEAD)),
+            None => Cell::new(x, y, STATUS::DEAD),
         }
     }
 
-    pub fn inverse_cell(&mut self, x: i32, y: i32) -> Option<&Cell> {
-        let c = self.rows.get_mut(y as usize)?.get_mut(x as usize)?;
+    pub fn inverse_cell(&mut self, x: i32, y: i32) -> Cell {
+        let status = self.get_cell(x, y).status.inverse();
 
-        if !c.is_alive() {
-            c.status = c.status.inverse();
-            self.actual.insert(*c);
-        } else {
-            self.actual.remove(c);
-            c.status = c.status.inverse();
-        }
-        Some(c)
+        self.set_cell(x, y, status)
     }
 
-    pub fn set_cell(&mut self, x: i32, y: i32, status: STATUS) -> Option<&Cell> {
-        let c = self.rows.get_mut(y as usize)?.get_mut(x as usize)?;
-
-        if c.status == status {
-            Some(c)
-        } else {
-            c.status = status;
-
-            if status.is_alive() {
-            self.actual.insert(*c);
-            } else {
-            self.actual.remove(c);
+    /// Set the cell at this position, following the edges of the board
+    /// Nothing is set outside a bounded plane, or in a state the rule doesn't have
+    pub fn set_cell(&mut self, x: i32, y: i32, status: STATUS) -> Cell {
+        let (x, y) = match self.rule.get_topology().wrap(x, y) {
+            Some(pos) if status.get_state() < self.rule.get_states() => pos,
+            _ => return self.get_cell(x, y),
+        };
+        let c = Cell::new(x, y, status);
+        let previous = self.get_cell(x, y);
+
+        if previous != c {
+            self.edited = true;
+            if !previous.status.is_dead() {
+                self.actual.remove(&previous);
+                self.history.record_edit(previous, true);
+            }
+            if !status.is_dead() {
+                self.actual.insert(c);
+                self.history.record_edit(c, false);
             }
-            Some(c)
         }
+        c
     }
 
     /// Get all adjacent cells status
-    /// There for we make a square around the original cell
+    /// There for we make a square around the original cell, wrapped on the edges of the board.
+    /// Outside of a bounded plane the cells are dead, they can't become alive.
     fn get_adj_cells(&self, pos: &Cell) -> Vec<Cell> {
-        vec![self.get_cell_or_dead(pos.x - 1, pos.y - 1),
-             self.get_cell_or_dead(pos.x    , pos.y - 1),
-             self.get_cell_or_dead(pos.x + 1, pos.y - 1),
-
-             self.get_cell_or_dead(pos.x - 1, pos.y),
-             self.get_cell_or_dead(pos.x + 1, pos.y),
-
-             self.get_cell_or_dead(pos.x - 1, pos.y  + 1),
-             self.get_cell_or_dead(pos.x    , pos.y  + 1),
-             self.get_cell_or_dead(pos.x + 1, pos.y  + 1)]
+        ADJ_POSITIONS.iter()
+            .filter_map(|(dx, dy)| self.rule.get_topology().wrap(pos.x + dx, pos.y + dy))
+            .map(|(x, y)| self.get_cell(x, y))
+            .collect()
     }
 
-    /// Apply the game of life rules on a certain position on the board
+    /// Apply the board rule on a certain position on the board
     fn apply_on_pos(&self, cell: &Cell) -> Cell {
-        let adj_live_cells = self.get_adj_cells(cell).iter()
-            .filter(|&&elem| elem.is_alive()).count();
-        cell.apply_rules(adj_live_cells)
+        let neighbourhood = self.get_neighbourhood(cell.x, cell.y);
+
+        match self.rule.get_probabilities() {
+            Some(_) => {
+                let chance = stochastic::chance(self.seed, self.generation, cell.x, cell.y);
+                Cell::new(cell.x, cell.y, self.rule.next_random_status(cell.status, neighbourhood, chance))
+            }
+            None => cell.apply_rules(neighbourhood, &self.rule),
+        }
     }
 
-    /// Get all active cells on the board
+    /// Get all active cells on the board, the dying cells and the living ones with their neighbours
     fn get_actual_interest_cell(&self) -> HashSet<Cell>{
         self.actual.iter()
+            .filter(|cell| cell.is_alive())
             .flat_map(|cell| self.get_adj_cells(cell))
+            .chain(self.actual.iter().copied())
             .collect()
     }
 
-    /// Apply the game of life rules on the board
+    /// Get the next cells of a Larger than Life rule, a rule table or WireWorld, None for the rules of the adjacent cells
+    /// A Larger than Life rule counts its neighbours from summed-area tables.
+    fn next_special(&self, parallel: bool) -> Option<HashSet<Cell>> {
+        if let Some(ltl) = self.rule.get_larger_than_life() {
+            Some(ltl::next(&self.actual, &self.rule, ltl, parallel))
+        } else if self.rule.is_wireworld() {
+            Some(wireworld::next(&self.actual, &self.rule, parallel))
+        } else {
+            self.rule.get_table().map(|rule_table| table::next(&self.actual, &self.rule, rule_table, parallel))
+        }
+    }
+
+    /// Apply the board rule on the board
     pub fn next(&mut self) {
-        let res = self.get_actual_interest_cell().iter()
+        let res = self.next_special(false).unwrap_or_else(|| self.get_actual_interest_cell().iter()
             .map(|&cell| self.apply_on_pos(&cell))
-            .filter(|&cell| cell.is_alive())
-            .collect::<Vec<Cell>>();
+            .filter(|&cell| !cell.status.is_dead())
+            .collect::<HashSet<Cell>>());
+
+        self.history.push(self.generation, &self.actual, &res);
+        self.actual = res;
+        self.generation += 1;
+    }
 
+    /// Get the living neighbours of a position as bits, the bit i is the neighbour `ADJ_POSITIONS[i]`
+    /// The list of the adjacent cells is not built, so it is looked up in one go in the rule.
+    fn get_neighbourhood(&self, x: i32, y: i32) -> u8 {
+        ADJ_POSITIONS.iter().enumerate()
+            .filter(|(_, (dx, dy))| match self.rule.get_topology().wrap(x + dx, y + dy) {
+                Some((x, y)) => self.actual.contains(&Cell::new(x, y, STATUS::ALIVE)),
+                None => false,
+            })
+            .fold(0, |neighbourhood, (i, _)| neighbourhood | (1 << i))
+    }
 
-        self.actual.clone().iter().for_each(|cell| { self.set_cell(cell.x, cell.y, STATUS::DEAD); });
-        self.history.push_front(self.actual.clone());
-        self.actual = Box::new(HashSet::new());
+    /// Apply the board rule on the board, by bands of `BAND_HEIGHT` rows computed on all the threads
+    /// Each band looks at its cells and the neighbours of the living ones, a neighbour in the next band
+    /// can be computed twice, but it gives the same state.
+    pub fn next_parallel(&mut self) {
+        if let Some(res) = self.next_special(true) {
+            self.history.push(self.generation, &self.actual, &res);
+            self.actual = res;
+            self.generation += 1;
+            return;
+        }
+        let mut bands: BTreeMap<i32, Vec<Cell>> = BTreeMap::new();
+        self.actual.iter().for_each(|&cell| bands.entry(cell.y.div_euclid(BAND_HEIGHT)).or_default().push(cell));
+        let bands: Vec<Vec<Cell>> = bands.into_values().collect();
+
+        let board = &*self;
+        let topology = *self.rule.get_topology();
+        let res = bands.par_iter()
+            .flat_map_iter(|cells| {
+                let interest: HashSet<(i32, i32)> = cells.iter()
+                    .filter(|cell| cell.is_alive())
+                    .flat_map(|cell| ADJ_POSITIONS.iter().filter_map(move |(dx, dy)| topology.wrap(cell.x + dx, cell.y + dy)))
+                    .chain(cells.iter().map(|cell| (cell.x, cell.y)))
+                    .collect();
+                interest.into_iter()
+                    .map(move |(x, y)| board.apply_on_pos(&board.get_cell(x, y)))
+                    .filter(|cell| !cell.status.is_dead())
+            })
+            .collect::<HashSet<Cell>>();
+
+        self.history.push(self.generation, &self.actual, &res);
+        self.actual = res;
+        self.generation += 1;
+    }
 
-        res.iter().for_each(|cell| { self.set_cell(cell.x, cell.y, cell.status); });
-        if self.history.len() >= 10 {
-            self.history.pop_back();
+    /// Advance one generation, in parallel or not
+    fn advance(&mut self) {
+        if self.parallel {
+            self.next_parallel();
+        } else {
+            self.next();
         }
     }
 
     pub fn prev(&mut self) {
-        if self.history.len() > 0 {
-            self.actual.clone().iter().for_each(|&cell| { self.set_cell(cell.x,  cell.y, STATUS::DEAD); });
+        if self.history.pop(&mut self.actual) {
+            self.generation -= 1;
+        }
+    }
 
-            self.actual = self.history.pop_front().unwrap();
-            self.actual.clone().iter().for_each(|&cell| { self.set_cell(cell.x, cell.y, cell.status); })
+    /// Go to a generation, forward by stepping, backward by restoring it from the history,
+    /// or by stepping from the initial cells when the history doesn't go back that far.
+    /// False when the generation is before the initial one, or before the history once a cell was set by hand,
+    /// stepping from the initial cells would lose the edits.
+    pub fn goto(&mut self, generation: u64) -> bool {
+        if generation < self.metadata.generation {
+            return false;
+        }
+        if generation < self.generation {
+            if self.history.restore(generation, &mut self.actual) {
+                self.generation = generation;
+                return true;
+            }
+            if self.edited {
+                return false;
+            }
+            self.actual = self.initial_state.clone();
+            self.generation = self.metadata.generation;
+            self.history.clear();
         }
+        while self.generation < generation {
+            self.advance();
+        }
+        true
+    }
+
+    #[inline]
+    pub fn get_history_depth(&self) -> HistoryDepth {
+        self.history.get_depth()
+    }
+
+    /// Change how many generations `prev` can undo
+    pub fn set_history_depth(&mut self, depth: HistoryDepth) {
+        self.history.set_depth(depth);
+    }
+}
+
+impl LifeEngine for Board {
+    fn step(&mut self) {
+        self.advance();
+    }
+
+    fn step_n(&mut self, n: u64) {
+        (0..n).for_each(|_| self.advance());
+    }
+
+    fn prev(&mut self) {
+        Board::prev(self);
+    }
+
+    fn get_cell(&self, x: i32, y: i32) -> Cell {
+        Board::get_cell(self, x, y)
+    }
+
+    fn set_cell(&mut self, x: i32, y: i32, status: STATUS) -> Cell {
+        Board::set_cell(self, x, y, status)
     }
-}
\ No newline at end of file
+
+    fn goto(&mut self, generation: u64) -> bool {
+        Board::goto(self, generation)
+    }
+
+    fn set_history_depth(&mut self, depth: HistoryDepth) {
+        Board::set_history_depth(self, depth);
+    }
+
+    fn get_cells_in(&self, (min_x, min_y, max_x, max_y): (i32, i32, i32, i32)) -> Vec<Cell> {
+        self.actual.iter()
+            .filter(|cell| cell.x >= min_x && cell.x <= max_x && cell.y >= min_y && cell.y <= max_y)
+            .copied()
+            .collect()
+    }
+
+    fn get_initial_cells(&self) -> Vec<Cell> {
+        self.initial_state.iter().copied().collect()
+    }
+
+    fn get_population(&self) -> u64 {
+        self.actual.len() as u64
+    }
+
+    fn get_bounding_box(&self) -> Option<(i32, i32, i32, i32)> {
+        Board::get_bounding_box(self)
+    }
+
+    fn get_generation(&self) -> u64 {
+        self.generation
+    }
+
+    fn get_rule(&self) -> &Rule {
+        &self.rule
+    }
+
+    fn get_metadata(&self) -> &PatternMetadata {
+        &self.metadata
+    }
+
+    fn get_seed(&self) -> Option<u64> {
+        Some(self.seed).filter(|_| self.rule.get_probabilities().is_some())
+    }
+
+    /// Get a copy of the board, with its generation, its history and its seed
+    fn to_board(&self) -> Board {
+        self.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cell::fixtures::{board, positions, sorted_states};
+
+    fn blinker() -> Board {
+        board(&[(0, 1), (1, 1), (2, 1)], Rule::default())
+    }
+
+    #[test]
+    fn blinker_oscillates() {
+        let mut board = blinker();
+
+        board.next();
+        assert_eq!(positions(&board), vec![(1, 0), (1, 1), (1, 2)]);
+        board.next();
+        assert_eq!(positions(&board), vec![(0, 1), (1, 1), (2, 1)]);
+    }
+
+    #[test]
+    fn glider_moves_into_negative_positions() {
+        // The glider goes a cell up and left every 4 generations
+        let mut board = board(&[(0, 0), (1, 0), (2, 0), (0, 1), (1, 2)], Rule::default());
+        (0..40).for_each(|_| board.next());
+
+        assert_eq!(positions(&board), vec![(-10, -10), (-10, -9), (-9, -10), (-9, -8), (-8, -10)]);
+    }
+
+    #[test]
+    fn far_apart_cells_step_alone() {
+        let far = 1_000_000_000;
+        let mut board = board(&[(-far, 0), (-far + 1, 0), (-far + 2, 0), (far, far), (far + 1, far), (far, far + 1), (far + 1, far + 1)],
+                              Rule::default());
+        board.next();
+
+        assert_eq!(positions(&board), vec![(-far + 1, -1), (-far + 1, 0), (-far + 1, 1),
+                                           (far, far), (far, far + 1), (far + 1, far), (far + 1, far + 1)]);
+    }
+
+    #[test]
+    fn goto_replays_from_initial_cells() {
+        let mut board = blinker();
+        board.set_history_depth(HistoryDepth::Limited(2));
+        board.goto(21);
+
+        assert!(board.goto(3));
+        assert_eq!(board.get_generation(), 3);
+        assert_eq!(positions(&board), vec![(1, 0), (1, 1), (1, 2)]);
+    }
+
+    #[test]
+    fn goto_keeps_cells_set_by_hand() {
+        let mut board = blinker();
+        board.set_history_depth(HistoryDepth::Limited(2));
+        board.goto(5);
+        board.set_cell(10, 10, STATUS::ALIVE);
+        board.goto(20);
+        let cells = positions(&board);
+
+        assert!(!board.goto(10));
+        assert_eq!(board.get_generation(), 20);
+        assert_eq!(positions(&board), cells);
+        assert!(board.goto(18));
+        assert_eq!(board.get_generation(), 18);
+    }
+
+    #[test]
+    fn parallel_matches_serial() {
+        // Gliders and blinkers on the edges of the bands, going down through them
+        let mut cells = vec![];
+        for band in -3..3 {
+            let y = band * BAND_HEIGHT;
+            for &(dx, dy) in [(1, -2), (2, -1), (0, 0), (1, 0), (2, 0)].iter() {
+                cells.push((dx + 10 * band, y + dy));
+            }
+            cells.extend_from_slice(&[(40, y - 1), (40, y), (40, y + 1), (-30, y), (-29, y), (-28, y)]);
+        }
+        let mut serial = board(&cells, Rule::default());
+        let mut parallel = board(&cells, Rule::default()).with_parallel(true);
+
+        for generation in 1..=2 * BAND_HEIGHT {
+            serial.step();
+            parallel.step();
+            assert_eq!(positions(&parallel), positions(&serial), "generation {}", generation);
+        }
+    }
+
+    #[test]
+    fn brians_brain_dying_cells() {
+        let cells = [Cell::new(-1, 0, STATUS::ALIVE), Cell::new(0, 0, STATUS::DYING(2)), Cell::new(1, 0, STATUS::ALIVE)];
+        let mut board = Board::new(&cells, "/2/3".parse().unwrap());
+
+        // The dying cell has 2 living neighbours but isn't born again, and isn't counted by (-1, -1)
+        board.step();
+        assert_eq!(sorted_states(board.get_leaving_cells()), vec![(0, -1, 1), (-1, 0, 2), (1, 0, 2), (0, 1, 1)]);
+        assert_eq!(board.get_cell(-1, 0).status, STATUS::DYING(2));
+        assert_eq!(board.get_cell(0, 0).status, STATUS::DEAD);
+
+        board.step();
+        assert_eq!(sorted_states(board.get_leaving_cells()), vec![(0, -1, 2), (0, 0, 1), (0, 1, 2)]);
+    }
+
+    /// Get the living cells after one generation of a rule
+    fn next_positions(cells: &[(i32, i32)], rule: &str) -> Vec<(i32, i32)> {
+        let mut board = board(cells, rule.parse().unwrap());
+        board.step();
+        positions(&board)
+    }
+
+    #[test]
+    fn von_neumann_births() {
+        assert_eq!(next_positions(&[(0, 0)], "B1/SV"), vec![(-1, 0), (0, -1), (0, 1), (1, 0)]);
+    }
+
+    #[test]
+    fn hexagonal_births_skip_two_corners() {
+        assert_eq!(next_positions(&[(0, 0)], "B1/SH"), vec![(-1, -1), (-1, 0), (0, -1), (0, 1), (1, 0), (1, 1)]);
+    }
+
+    #[test]
+    fn hexagonal_survival_ignores_two_corners() {
+        // (1, -1) and (-1, 1) are not neighbours of (0, 0) on the hexagonal grid
+        assert_eq!(next_positions(&[(-1, 1), (0, 0), (1, -1)], "B/S2"), vec![(0, 0)]);
+        assert!(next_positions(&[(-1, 1), (0, 0), (1, -1)], "B/S2H").is_empty());
+        assert_eq!(next_positions(&[(-1, -1), (0, 0), (1, 1)], "B/S2H"), vec![(0, 0)]);
+    }
+
+    #[test]
+    fn custom_mask_births() {
+        // @81 is the top left and bottom right cells
+        assert_eq!(next_positions(&[(0, 0)], "B1/S@81"), vec![(-1, -1), (1, 1)]);
+        assert_eq!(next_positions(&[(0, 0), (2, 0)], "B2/S@18"), vec![(1, 0)]);
+    }
+}
@@ -0,0 +1,84 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// Define which adjacent cells are neighbours, written like Golly after the rule: `B2/S34H`
+/// A neighbourhood is a mask of the 8 adjacent cells, the bit i is the cell `ADJ_POSITIONS[i]`.
+/// <p> - Moore: the 8 adjacent cells, the default </p>
+/// <p> - VonNeumann `V`: the 4 orthogonal cells </p>
+/// <p> - Hexagonal `H`: the 6 cells of a hexagonal grid drawn on the square one,
+///  all the adjacent cells but the top right and bottom left corners </p>
+/// <p> - Custom `@5a`: the mask in hexadecimal, like `@5a` for von Neumann </p>
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Hash, Default)]
+pub enum Neighbourhood {
+    #[default]
+    Moore,
+    VonNeumann,
+    Hexagonal,
+    Custom(u8),
+}
+
+impl Neighbourhood {
+    /// Get the mask of the adjacent cells that are neighbours
+    pub fn get_mask(&self) -> u8 {
+        match *self {
+            Neighbourhood::Moore => 0xff,
+            Neighbourhood::VonNeumann => 0x5a,
+            Neighbourhood::Hexagonal => 0xdb,
+            Neighbourhood::Custom(mask) => mask,
+        }
+    }
+
+    /// Get the number of neighbours
+    pub fn get_size(&self) -> usize {
+        self.get_mask().count_ones() as usize
+    }
+
+    #[inline]
+    pub fn is_hexagonal(&self) -> bool {
+        *self == Neighbourhood::Hexagonal
+    }
+
+    /// Split the neighbourhood suffix of a rule, like `B2/S34H`, from the rule
+    pub fn split_rule(rule: &str) -> Result<(&str, Self), String> {
+        if let Some((rule, mask)) = rule.split_once('@') {
+            return Ok((rule, format!("@{}", mask).parse()?));
+        }
+        match rule.char_indices().last() {
+            Some((i, c)) if "VvHh".contains(c) => Ok((&rule[..i], c.to_string().parse()?)),
+            _ => Ok((rule, Neighbourhood::Moore)),
+        }
+    }
+}
+
+/// Read the suffix of a rule: `V`, `H` or `@` and a hexadecimal mask, nothing for Moore
+impl FromStr for Neighbourhood {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+
+        match s {
+            "" => Ok(Neighbourhood::Moore),
+            "V" | "v" => Ok(Neighbourhood::VonNeumann),
+            "H" | "h" => Ok(Neighbourhood::Hexagonal),
+            _ => match s.strip_prefix('@').map(|mask| u8::from_str_radix(mask, 16)) {
+                Some(Ok(0xff)) => Ok(Neighbourhood::Moore),
+                Some(Ok(0x5a)) => Ok(Neighbourhood::VonNeumann),
+                Some(Ok(0xdb)) => Ok(Neighbourhood::Hexagonal),
+                Some(Ok(mask)) => Ok(Neighbourhood::Custom(mask)),
+                _ => Err(format!("Invalid neighbourhood '{}', expected V, H or a mask like @5a", s)),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Neighbourhood {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Neighbourhood::Moore => Ok(()),
+            Neighbourhood::VonNeumann => write!(f, "V"),
+            Neighbourhood::Hexagonal => write!(f, "H"),
+            Neighbourhood::Custom(mask) => write!(f, "@{:02x}", mask),
+        }
+    }
+}
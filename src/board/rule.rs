@@ -0,0 +1,553 @@
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::board::cell::STATUS;
+use crate::board::ltl::LargerThanLife;
+use crate::board::neighbourhood::Neighbourhood;
+use crate::board::stochastic::Probabilities;
+use crate::board::table::{self, RuleTable};
+use crate::board::topology::Topology;
+use crate::board::{wireworld, ADJ_POSITIONS};
+
+/// The Hensel letters of the neighbourhoods of 0 to 4 living neighbours, in Golly's order
+/// The neighbourhoods of 5 to 8 living neighbours have the letter of their inverse.
+const LETTERS: [&str; 5] = ["", "ce", "ceaikn", "ceaiknjqry", "ceaiknjqrtwyz"];
+
+/// A neighbourhood of each letter, as the bits of the 3x3 square read by rows, the centre is the bit 4
+const LETTER_NEIGHBOURHOODS: [&[u16]; 5] = [
+    &[0],
+    &[1, 2],
+    &[5, 10, 3, 40, 33, 68],
+    &[69, 42, 11, 7, 98, 13, 14, 70, 41, 97],
+    &[325, 170, 15, 45, 99, 71, 106, 102, 43, 101, 105, 78, 108],
+];
+
+/// The 256 neighbourhoods a rule gives a result for, the bit n is set when the neighbourhood n does.
+/// The bit i of a neighbourhood is set when the neighbour `ADJ_POSITIONS[i]` is alive.
+type Neighbourhoods = [u64; 4];
+
+#[inline]
+fn contains(neighbourhoods: &Neighbourhoods, neighbourhood: u8) -> bool {
+    neighbourhoods[neighbourhood as usize / 64] & (1 << (neighbourhood % 64)) != 0
+}
+
+#[inline]
+fn insert(neighbourhoods: &mut Neighbourhoods, neighbourhood: u8) {
+    neighbourhoods[neighbourhood as usize / 64] |= 1 << (neighbourhood % 64);
+}
+
+/// Get the letters of a number of living neighbours, with a neighbourhood of each
+fn letters(count: usize) -> Vec<(char, u8)> {
+    // The centre bit is dropped
+    let to_neighbourhood = |bits: u16| ((bits & 0xf) | ((bits >> 1) & 0xf0)) as u8;
+
+    if count <= 4 {
+        LETTERS[count].chars().zip(LETTER_NEIGHBOURHOODS[count].iter().map(|&bits| to_neighbourhood(bits))).collect()
+    } else {
+        letters(8 - count).into_iter().map(|(letter, neighbourhood)| (letter, !neighbourhood)).collect()
+    }
+}
+
+/// Get a neighbourhood with all its rotations and reflections
+fn symmetries(neighbourhood: u8) -> Vec<u8> {
+    // A quarter turn the number of times, then a mirror for the symmetries 4 to 7
+    let transform = |(x, y): (i32, i32), symmetry: usize| {
+        let (x, y) = (0..symmetry % 4).fold((x, y), |(x, y), _| (-y, x));
+        if symmetry >= 4 { (-x, y) } else { (x, y) }
+    };
+
+    (0..8).map(|symmetry| {
+        (0..8).filter(|i| neighbourhood & (1 << i) != 0)
+            .map(|i| transform(ADJ_POSITIONS[i], symmetry))
+            .map(|pos| ADJ_POSITIONS.iter().position(|&adj| adj == pos).unwrap())
+            .fold(0, |res, i| res | (1 << i))
+    }).collect()
+}
+
+/// Define an isotropic rule, written `B3/S23`, `23/3`, or in the Hensel notation like `B2n3/S23-q`
+/// <p> - birth: the neighbourhoods where a dead cell becomes alive </p>
+/// <p> - survival: the neighbourhoods where a living cell stays alive </p>
+/// <p> - states: the number of states of a Generations rule like `B2/S/C3`, 2 for life-like rules.
+///  A living cell that doesn't survive goes through the dying states before it is dead. </p>
+/// <p> - probabilities: the chances of a birth and of a survival of a stochastic rule like `B3/S23/P0.9,0.99`,
+///  drawn from the seed of the board </p>
+/// <p> - neighbourhood: the adjacent cells counted as neighbours, given after the rule like `B2/S34H` </p>
+/// <p> - topology: the shape of the board, given after the rule like `B3/S23:T100,80` </p>
+/// <p> - larger_than_life: the counts of a Larger than Life rule like `R5,C0,M1,S34..58,B34..45,NM`,
+///  used instead of the neighbourhoods </p>
+/// <p> - table: the transitions of a Golly `.rule` file like `LifeTable`, shared by the copies of the rule </p>
+/// <p> - table_name: the name of a rule table read from a rule string, the table is loaded by `load_table` </p>
+/// <p> - wireworld: the built-in WireWorld, with its 4 states and its own transitions </p>
+#[derive(Clone, Eq, PartialEq, Debug, Hash)]
+pub struct Rule {
+    birth: Neighbourhoods,
+    survival: Neighbourhoods,
+    states: u8,
+    probabilities: Option<Probabilities>,
+    neighbourhood: Neighbourhood,
+    topology: Topology,
+    larger_than_life: Option<LargerThanLife>,
+    table: Option<Arc<RuleTable>>,
+    table_name: Option<String>,
+    wireworld: bool,
+}
+
+impl Rule {
+    /// Construct an outer-totalistic rule from the numbers of living neighbours
+    pub fn new(birth: &[usize], survival: &[usize]) -> Self {
+        let counts = |counts: &[usize]| (0..=255u8)
+            .filter(|neighbourhood| counts.contains(&(neighbourhood.count_ones() as usize)))
+            .fold([0; 4], |mut neighbourhoods, neighbourhood| { insert(&mut neighbourhoods, neighbourhood); neighbourhoods });
+
+        Rule { birth: counts(birth), survival: counts(survival), states: 2, probabilities: None, neighbourhood: Neighbourhood::Moore,
+               topology: Topology::Plane, larger_than_life: None, table: None, table_name: None, wireworld: false }
+    }
+
+    /// Construct a Larger than Life rule, with two states on the plane
+    pub fn larger_than_life(larger_than_life: LargerThanLife) -> Self {
+        Rule { larger_than_life: Some(larger_than_life), ..Rule::new(&[], &[]) }
+    }
+
+    /// Construct a rule from a rule table, with its states and neighbours, on the plane
+    pub fn table(table: RuleTable) -> Self {
+        Rule { states: table.get_states(), neighbourhood: table.get_neighbourhood(), table: Some(Arc::new(table)), ..Rule::new(&[], &[]) }
+    }
+
+    /// Construct a rule from the name of a rule table, it runs once the table is loaded by `load_table`
+    pub fn named_table(name: &str) -> Self {
+        Rule { table_name: Some(name.to_string()), ..Rule::new(&[], &[]) }
+    }
+
+    /// Load the table of a rule read by its name, from `RULES_FOLDER`, the other rules are kept as they are
+    pub fn load_table(self) -> Result<Self, String> {
+        match &self.table_name {
+            Some(name) => Ok(Rule::table(RuleTable::load(name)?).with_topology(self.topology)),
+            None => Ok(self),
+        }
+    }
+
+    /// Construct WireWorld, with its empty, head, tail and conductor states, on the plane
+    pub fn wireworld() -> Self {
+        Rule { states: wireworld::STATES, wireworld: true, ..Rule::new(&[], &[]) }
+    }
+
+    /// The original rule of the game of life
+    pub fn conway() -> Self {
+        Rule::new(&[3], &[2, 3])
+    }
+
+    /// Get the same rule on another board shape
+    pub fn with_topology(self, topology: Topology) -> Self {
+        Rule { topology, ..self }
+    }
+
+    /// Get the same rule with another number of states, at least 2
+    pub fn with_states(self, states: u8) -> Self {
+        Rule { states: states.max(2), ..self }
+    }
+
+    /// Get the same rule with births and survivals that only happen with these probabilities
+    pub fn with_probabilities(self, probabilities: Probabilities) -> Self {
+        Rule { probabilities: Some(probabilities), ..self }
+    }
+
+    /// Get the same rule with other neighbours, the counts above the number of neighbours never happen
+    pub fn with_neighbourhood(self, neighbourhood: Neighbourhood) -> Self {
+        Rule { neighbourhood, ..self }
+    }
+
+    #[inline]
+    pub fn get_topology(&self) -> &Topology {
+        &self.topology
+    }
+
+    #[inline]
+    pub fn get_neighbourhood(&self) -> &Neighbourhood {
+        &self.neighbourhood
+    }
+
+    #[inline]
+    pub fn get_probabilities(&self) -> Option<&Probabilities> {
+        self.probabilities.as_ref()
+    }
+
+    #[inline]
+    pub fn get_larger_than_life(&self) -> Option<&LargerThanLife> {
+        self.larger_than_life.as_ref()
+    }
+
+    #[inline]
+    pub fn get_table(&self) -> Option<&RuleTable> {
+        self.table.as_deref()
+    }
+
+    /// Get the name of the rule table, loaded or not
+    pub fn get_table_name(&self) -> Option<&str> {
+        self.table_name.as_deref().or_else(|| self.get_table().map(RuleTable::get_name))
+    }
+
+    #[inline]
+    pub fn is_wireworld(&self) -> bool {
+        self.wireworld
+    }
+
+    /// Is the rule given by its birth and survival neighbourhoods,
+    /// not a Larger than Life rule, a rule table or WireWorld, computed by their own steps
+    pub fn uses_neighbourhoods(&self) -> bool {
+        self.larger_than_life.is_none() && self.get_table_name().is_none() && !self.wireworld
+    }
+
+    /// Get the colour of a state when the rule has its own, like the `@COLORS` of a rule table or WireWorld's
+    pub fn get_color(&self, state: u8) -> Option<(u8, u8, u8)> {
+        if self.wireworld {
+            return wireworld::COLORS.get(state as usize).copied();
+        }
+        self.table.as_ref().and_then(|table| table.get_color(state))
+    }
+
+    /// Get how far the neighbours are, 1 for the rules of the adjacent cells
+    pub fn get_range(&self) -> u32 {
+        self.larger_than_life.map_or(1, |larger_than_life| larger_than_life.get_range())
+    }
+
+    /// Get the number of states, 2 for the life-like rules
+    #[inline]
+    pub fn get_states(&self) -> u8 {
+        self.states
+    }
+
+    /// Give the next state of a cell from its state and its living adjacent cells
+    /// The bit i of the neighbourhood is set when the cell `ADJ_POSITIONS[i]` is alive,
+    /// the cells outside the neighbourhood of the rule are ignored.
+    #[inline]
+    pub fn apply(&self, alive: bool, neighbourhood: u8) -> bool {
+        let neighbourhood = neighbourhood & self.neighbourhood.get_mask();
+        if alive {
+            contains(&self.survival, neighbourhood)
+        } else {
+            contains(&self.birth, neighbourhood)
+        }
+    }
+
+    /// Give the next state of a cell from its state and its number of living neighbours
+    /// Only for the outer-totalistic rules of the Moore neighbourhood, where the place of the neighbours doesn't matter.
+    pub fn apply_count(&self, alive: bool, adj_live_cells: usize) -> bool {
+        adj_live_cells <= 8 && self.apply(alive, ((1u16 << adj_live_cells) - 1) as u8)
+    }
+
+    /// Does the rule only depend on the number of living neighbours of the Moore neighbourhood
+    pub fn is_totalistic(&self) -> bool {
+        if self.neighbourhood != Neighbourhood::Moore || !self.uses_neighbourhoods() {
+            return false;
+        }
+        let totalistic = |neighbourhoods: &Neighbourhoods| (0..=255u8).all(|neighbourhood| {
+            let first = ((1u16 << neighbourhood.count_ones()) - 1) as u8;
+            contains(neighbourhoods, neighbourhood) == contains(neighbourhoods, first)
+        });
+
+        totalistic(&self.birth) && totalistic(&self.survival)
+    }
+
+    /// Give the next status of a cell from its status and its living neighbours
+    /// A living cell that doesn't survive, and a dying cell, go to the next state, and dead after the last one.
+    pub fn next_status(&self, status: STATUS, neighbourhood: u8) -> STATUS {
+        self.next_status_from(status, self.apply(status == STATUS::ALIVE, neighbourhood))
+    }
+
+    /// Give the next status of a cell of a stochastic rule from its status, its living neighbours
+    /// and a number drawn below a million for it, a birth or a survival only happens when it is below its probability
+    pub fn next_random_status(&self, status: STATUS, neighbourhood: u8, chance: u32) -> STATUS {
+        let alive = status == STATUS::ALIVE;
+        let probable = self.probabilities.is_none_or(|probabilities| probabilities.apply(alive, chance));
+
+        self.next_status_from(status, probable && self.apply(alive, neighbourhood))
+    }
+
+    /// Give the next status of a cell from its status and if the rule makes it alive, when it isn't dying
+    pub fn next_status_from(&self, status: STATUS, alive: bool) -> STATUS {
+        match status {
+            STATUS::DEAD if alive => STATUS::ALIVE,
+            STATUS::DEAD => STATUS::DEAD,
+            STATUS::ALIVE if alive => STATUS::ALIVE,
+            STATUS::ALIVE | STATUS::DYING(_) => match status.get_state() + 1 {
+                state if state < self.states => STATUS::DYING(state),
+                _ => STATUS::DEAD,
+            },
+        }
+    }
+
+    /// Get the rule in the old `S/B` notation, like `23/3`, `/2/3`, `23/3/P0.9,0.99` or `23/3:T100,80`
+    /// A Larger than Life rule has only its Golly notation, and a rule table or WireWorld its name.
+    pub fn get_sb_notation(&self) -> String {
+        if !self.uses_neighbourhoods() {
+            return self.to_string();
+        }
+        format!("{}/{}{}{}{}{}", Rule::notation(&self.survival), Rule::notation(&self.birth), self.states_notation(""),
+                self.probabilities_notation(), self.neighbourhood, self.topology)
+    }
+
+    /// Write the number of states after a slash, nothing for the life-like rules
+    fn states_notation(&self, prefix: &str) -> String {
+        if self.states > 2 { format!("/{}{}", prefix, self.states) } else { String::new() }
+    }
+
+    /// Write the probabilities after a slash, nothing for the rules without chance
+    fn probabilities_notation(&self) -> String {
+        self.probabilities.map_or(String::new(), |probabilities| format!("/P{}", probabilities.notation()))
+    }
+
+    /// Write the neighbourhoods as counts with their Hensel letters, like `236` or `2n3-q`
+    /// The letters are the shortest of the ones kept and the ones removed with a `-`.
+    fn notation(neighbourhoods: &Neighbourhoods) -> String {
+        let mut res = String::new();
+
+        for count in 0..=8 {
+            // 0 and 8 neighbours have a single neighbourhood, without letter
+            let letters = match count {
+                0 => vec![(' ', 0)],
+                8 => vec![(' ', 0xff)],
+                _ => letters(count),
+            };
+            let (kept, removed): (Vec<_>, Vec<_>) = letters.iter()
+                .partition(|&&(_, neighbourhood)| contains(neighbourhoods, neighbourhood));
+            let kept: String = kept.iter().map(|(letter, _)| *letter).collect();
+            let removed: String = removed.iter().map(|(letter, _)| *letter).collect();
+
+            match (kept.is_empty(), removed.is_empty()) {
+                (true, _) => {}
+                (false, true) => res += &count.to_string(),
+                _ if kept.len() <= removed.len() => res += &format!("{}{}", count, kept),
+                _ => res += &format!("{}-{}", count, removed),
+            }
+        }
+        res
+    }
+
+    /// Parse neighbour counts with their optional Hensel letters, like `236` or `2n3-q`
+    /// The letters are only for the Moore neighbourhood.
+    fn parse_neighbourhoods(s: &str, neighbours: Neighbourhood) -> Result<Neighbourhoods, String> {
+        let mut neighbourhoods = [0; 4];
+        let mut chars = s.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            let count = match c.to_digit(10) {
+                Some(n) if n as usize <= neighbours.get_size() => n as usize,
+                _ => return Err(format!("Invalid neighbour count '{}' in rule, there are {} neighbours", c, neighbours.get_size())),
+            };
+            let removed = chars.next_if_eq(&'-').is_some();
+            let mut kept: Vec<u8> = vec![];
+            while let Some(letter) = chars.next_if(|c| c.is_ascii_lowercase()) {
+                if neighbours != Neighbourhood::Moore {
+                    return Err(format!("Invalid neighbourhood '{}{}' in rule, the letters are only for the Moore neighbourhood", count, letter));
+                }
+                match letters(count).into_iter().find(|&(l, _)| l == letter) {
+                    Some((_, neighbourhood)) => kept.extend(symmetries(neighbourhood)),
+                    None => return Err(format!("Invalid neighbourhood '{}{}' in rule", count, letter)),
+                }
+            }
+            (0..=255u8)
+                .filter(|neighbourhood| neighbourhood.count_ones() as usize == count)
+                .filter(|neighbourhood| kept.is_empty() || kept.contains(neighbourhood) != removed)
+                .for_each(|neighbourhood| insert(&mut neighbourhoods, neighbourhood));
+        }
+        Ok(neighbourhoods)
+    }
+
+    /// Parse the number of states of a Generations rule, from 2 to 255
+    fn parse_states(s: &str) -> Result<u8, String> {
+        match s.trim().parse::<u8>() {
+            Ok(states) if states >= 2 => Ok(states),
+            _ => Err(format!("Invalid number of states '{}' in rule, expected 2 to 255", s)),
+        }
+    }
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        Rule::conway()
+    }
+}
+
+/// Read the `B36/S23` notation, the letters can be swapped or lowercase.
+/// Without letters the old `S/B` notation is used, `23/36` is HighLife.
+/// The counts can be followed by Hensel letters, like `B2n3/S23-q`.
+/// The neighbourhood can follow the rule, like `B2/S34H` or `B13/S012V`.
+/// A Generations rule has its number of states in a third part, like `B2/S/C3` or `/2/3` for Brian's Brain.
+/// A stochastic rule has the probabilities of birth and survival in a `P` part, like `B3/S23/P0.9,0.99`.
+/// The topology can follow after a colon, like `B3/S23:T100,80`.
+/// A birth without living neighbours, `B0`, is refused: only the cells next to a living one are computed.
+/// A Larger than Life rule starts with its range, like `R5,C0,M1,S34..58,B34..45,NM`.
+/// `WireWorld` is built in, like `WireWorld:T100,80`.
+/// Another name is a rule table, like `LifeTable` or `LifeTable.rule`, only the name is read: the `.rule` file is loaded by `load_table`.
+impl FromStr for Rule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (s, topology) = match s.trim().split_once(':') {
+            Some((s, topology)) => (s, topology.parse()?),
+            None => (s, Topology::Plane),
+        };
+        let s = s.trim();
+        let range = s.strip_prefix(['R', 'r']).is_some_and(|s| s.starts_with(|c: char| c.is_ascii_digit()));
+        if range {
+            let (larger_than_life, states) = LargerThanLife::parse(s)?;
+            return Ok(Rule::larger_than_life(larger_than_life).with_states(states).with_topology(topology));
+        }
+        if s.eq_ignore_ascii_case(wireworld::NAME) {
+            return Ok(Rule::wireworld().with_topology(topology));
+        }
+        // `B3S23` is a B/S rule without its slash, not a table name
+        let name = s.strip_suffix(".rule").unwrap_or(s);
+        let bs = name.starts_with(|c: char| c.is_ascii_digit())
+            || name.strip_prefix(['B', 'b', 'S', 's']).is_some_and(|s| s.starts_with(|c: char| c.is_ascii_digit()));
+        if !s.contains('/') && !bs && table::is_name(name) {
+            return Ok(Rule::named_table(name).with_topology(topology));
+        }
+        let (s, neighbours) = Neighbourhood::split_rule(s)?;
+        let parts: Vec<&str> = s.split('/').collect();
+        if parts.len() < 2 || parts.len() > 4 {
+            return Err(format!("Invalid rule '{}', expected B3/S23, 23/3 or B2/S/C3", s));
+        }
+
+        let (mut birth, mut survival, mut states, mut probabilities) = (None, None, 2, None);
+        for (i, part) in parts.iter().enumerate() {
+            match part.chars().next() {
+                Some('P') | Some('p') => probabilities = Some(Probabilities::parse(&part[1..])?),
+                Some('B') | Some('b') => birth = Some(Rule::parse_neighbourhoods(&part[1..], neighbours)?),
+                Some('S') | Some('s') => survival = Some(Rule::parse_neighbourhoods(&part[1..], neighbours)?),
+                Some('C') | Some('c') => states = Rule::parse_states(&part[1..])?,
+                _ if i == 0 => survival = Some(Rule::parse_neighbourhoods(part, neighbours)?),
+                _ if i == 1 => birth = Some(Rule::parse_neighbourhoods(part, neighbours)?),
+                _ => states = Rule::parse_states(part)?,
+            }
+        }
+
+        match (birth, survival) {
+            (Some(birth), _) if contains(&birth, 0) =>
+                Err(String::from("Invalid birth 'B0' in rule, a birth needs a living neighbour")),
+            (Some(birth), Some(survival)) => Ok(Rule { birth, survival, probabilities, neighbourhood: neighbours, topology, ..Rule::default() }.with_states(states)),
+            _ => Err(format!("Invalid rule '{}', expected B3/S23, 23/3 or B2/S/C3", s))
+        }
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(larger_than_life) = self.larger_than_life {
+            return write!(f, "{}{}", larger_than_life.notation(self.states), self.topology);
+        }
+        if let Some(name) = self.get_table_name() {
+            return write!(f, "{}{}", name, self.topology);
+        }
+        if self.wireworld {
+            return write!(f, "{}{}", wireworld::NAME, self.topology);
+        }
+        write!(f, "B{}/S{}{}{}{}{}", Rule::notation(&self.birth), Rule::notation(&self.survival), self.states_notation("C"),
+               self.probabilities_notation(), self.neighbourhood, self.topology)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use crate::board::cell::Cell;
+    use crate::board::Board;
+
+    /// The neighbourhoods of each letter of a count, with all their symmetries
+    fn letter_sets(count: usize) -> Vec<(char, HashSet<u8>)> {
+        letters(count).into_iter()
+            .map(|(letter, neighbourhood)| (letter, symmetries(neighbourhood).into_iter().collect()))
+            .collect()
+    }
+
+    #[test]
+    fn letters_partition_each_count() {
+        for count in 1..=7 {
+            let mut seen = HashSet::new();
+            for (letter, set) in letter_sets(count) {
+                assert!(set.iter().all(|neighbourhood| neighbourhood.count_ones() as usize == count), "{}{}", count, letter);
+                assert!(set.iter().all(|&neighbourhood| seen.insert(neighbourhood)), "{}{} overlaps another letter", count, letter);
+            }
+            let all: HashSet<u8> = (0..=255u8).filter(|neighbourhood| neighbourhood.count_ones() as usize == count).collect();
+            assert_eq!(seen, all, "the letters of {} miss neighbourhoods", count);
+        }
+    }
+
+    #[test]
+    fn letters_are_closed_under_symmetries() {
+        for count in 1..=7 {
+            for (letter, set) in letter_sets(count) {
+                for &neighbourhood in set.iter() {
+                    assert!(symmetries(neighbourhood).iter().all(|symmetric| set.contains(symmetric)), "{}{}", count, letter);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn sb_notation_round_trip() {
+        for (rule, sb) in [("B2n3/S23-q", "23-q/2n3"), ("B3/S23", "23/3"), ("B36/S23", "23/36"), ("B2/S/C3", "/2/3")] {
+            let rule: Rule = rule.parse().unwrap();
+            assert_eq!(rule.get_sb_notation(), sb);
+            assert_eq!(sb.parse::<Rule>().unwrap(), rule);
+        }
+        assert_eq!("B2n3/S23-q".parse::<Rule>().unwrap().to_string(), "B2n3/S23-q");
+        assert!("B3/S23".parse::<Rule>().unwrap().is_totalistic());
+        assert!(!"B2n3/S23-q".parse::<Rule>().unwrap().is_totalistic());
+    }
+
+    #[test]
+    fn opposite_corners_give_birth_with_2n() {
+        let cells = [Cell::new(0, 0, STATUS::ALIVE), Cell::new(2, 2, STATUS::ALIVE)];
+        let next = |rule: &str| {
+            let mut board = Board::new(&cells, rule.parse().unwrap());
+            board.next();
+            let mut positions: Vec<_> = board.get_leaving_cells().iter().map(|cell| (cell.x, cell.y)).collect();
+            positions.sort_unstable();
+            positions
+        };
+
+        assert_eq!(next("B2n/S"), vec![(1, 1)]);
+        assert_eq!(next("B2/S"), vec![(1, 1)]);
+        assert!(next("B2-n/S").is_empty());
+        assert!(next("B2c/S").is_empty());
+    }
+
+    #[test]
+    fn table_names_are_read_without_loading() {
+        let rule: Rule = "LifeTable.rule:T10,10".parse().unwrap();
+
+        assert_eq!(rule.get_table_name(), Some("LifeTable"));
+        assert!(rule.get_table().is_none());
+        assert_eq!(rule.to_string(), "LifeTable:T10,10");
+        assert!("NoSuchTable".parse::<Rule>().is_ok());
+    }
+
+    #[test]
+    fn bs_typos_and_paths_are_not_table_names() {
+        for rule in ["B3S23", "23", "../../x.rule", "/etc/x.rule", "..", ""] {
+            assert!(rule.parse::<Rule>().is_err(), "{}", rule);
+        }
+    }
+
+    #[test]
+    fn load_table_from_rules_folder() {
+        let rule = "LifeTable:T10,10".parse::<Rule>().unwrap().load_table().unwrap();
+
+        assert_eq!(rule.get_table().map(RuleTable::get_name), Some("LifeTable"));
+        assert_eq!(rule.get_topology(), "B3/S23:T10,10".parse::<Rule>().unwrap().get_topology());
+        assert!(Rule::named_table("NoSuchTable").load_table().is_err());
+        assert!(RuleTable::load("../rules/LifeTable").is_err());
+    }
+
+    #[test]
+    fn birth_without_neighbours_is_refused() {
+        for rule in ["B0/S23", "B03/S23", "023/0", "B0/S/C3", "B0/S23V"] {
+            let error = rule.parse::<Rule>().unwrap_err();
+            assert!(error.contains("B0"), "{}: {}", rule, error);
+        }
+        assert!("B1/S012".parse::<Rule>().is_ok());
+    }
+}
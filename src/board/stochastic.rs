@@ -0,0 +1,126 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The probabilities are stored in millionths, so the rules can be compared and hashed
+const SCALE: u32 = 1_000_000;
+
+/// The probabilities of a stochastic rule, written after the rule like `B3/S23/P0.9,0.99`
+/// <p> - birth: the probability a dead cell is born when the rule makes it born </p>
+/// <p> - survival: the probability a living cell survives when the rule makes it survive </p>
+/// The other cells follow the rule, a cell is never born or kept alive by chance.
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Hash)]
+pub struct Probabilities {
+    birth: u32,
+    survival: u32,
+}
+
+impl Probabilities {
+    /// Parse the probabilities of birth and survival from 0 to 1, like `0.9,0.99`, a single one is for both
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let probability = |p: &str| match p.trim().parse::<f64>() {
+            Ok(p) if (0.0..=1.0).contains(&p) => Ok((p * SCALE as f64).round() as u32),
+            _ => Err(format!("Invalid probability '{}' in rule, expected 0 to 1 like P0.9,0.99", p.trim())),
+        };
+
+        match s.split_once(',') {
+            Some((birth, survival)) => Ok(Probabilities { birth: probability(birth)?, survival: probability(survival)? }),
+            None => probability(s).map(|p| Probabilities { birth: p, survival: p }),
+        }
+    }
+
+    /// Does a birth or a survival happen, from a number drawn below `SCALE` for the cell
+    #[inline]
+    pub fn apply(&self, alive: bool, chance: u32) -> bool {
+        chance < if alive { self.survival } else { self.birth }
+    }
+
+    /// Write the probabilities like `0.9,0.99`
+    pub fn notation(&self) -> String {
+        format!("{},{}", self.birth as f64 / SCALE as f64, self.survival as f64 / SCALE as f64)
+    }
+}
+
+/// Mix the bits of a number, the SplitMix64 finalizer
+#[inline]
+fn mix(mut z: u64) -> u64 {
+    z = z.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Draw the number below `SCALE` of a cell at a generation, from the seed of the board
+/// It is a hash of the seed, the generation and the position, so a generation gets the same cells
+/// whatever the order they are computed in, when it is computed again after `prev`, or replayed from the seed.
+#[inline]
+pub fn chance(seed: u64, generation: u64, x: i32, y: i32) -> u32 {
+    let position = ((x as u32 as u64) << 32) | y as u32 as u64;
+
+    (mix(seed ^ mix(generation ^ mix(position))) % SCALE as u64) as u32
+}
+
+/// Get a new seed from the clock, for a run without a chosen seed
+pub fn time_seed() -> u64 {
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or(0);
+
+    mix(nanos as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+    use crate::board::cell::fixtures::{board, positions};
+    use crate::board::history::HistoryDepth;
+    use crate::engine::LifeEngine;
+
+    /// A soup on a stochastic rule, with this seed
+    fn soup(seed: u64) -> Board {
+        let positions: Vec<(i32, i32)> = (0..400).filter(|i| mix(*i) & 1 == 0).map(|i| (i as i32 % 20, i as i32 / 20)).collect();
+
+        board(&positions, "B3/S23/P0.5,0.9".parse().unwrap()).with_seed(seed)
+    }
+
+    /// Get the cells of each generation up to `generations`
+    fn run(board: &mut Board, generations: u64) -> Vec<Vec<(i32, i32)>> {
+        (0..generations).map(|_| {
+            board.step();
+            positions(board)
+        }).collect()
+    }
+
+    #[test]
+    fn same_seed_same_cells() {
+        let cells = run(&mut soup(42), 20);
+
+        assert_eq!(run(&mut soup(42), 20), cells);
+        assert_ne!(run(&mut soup(43), 20), cells);
+        assert!(!cells[19].is_empty());
+    }
+
+    #[test]
+    fn goto_replays_the_same_generations() {
+        let cells = run(&mut soup(7), 20);
+        let mut board = soup(7);
+        board.set_history_depth(HistoryDepth::Limited(3));
+
+        assert!(board.goto(20));
+        assert_eq!(positions(&board), cells[19]);
+        // Before the history, the generations are replayed from the initial cells
+        assert!(board.goto(5));
+        assert_eq!(positions(&board), cells[4]);
+        assert!(board.goto(12));
+        assert_eq!(positions(&board), cells[11]);
+        board.prev();
+        assert_eq!(positions(&board), cells[10]);
+    }
+
+    #[test]
+    fn chance_depends_on_every_input() {
+        let drawn = chance(1, 2, 3, -4);
+
+        assert!(drawn < SCALE);
+        assert_ne!(chance(2, 2, 3, -4), drawn);
+        assert_ne!(chance(1, 3, 3, -4), drawn);
+        assert_ne!(chance(1, 2, -4, 3), drawn);
+    }
+}
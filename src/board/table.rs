@@ -0,0 +1,528 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use rayon::prelude::*;
+
+use crate::board::cell::{Cell, STATUS};
+use crate::board::neighbourhood::Neighbourhood;
+use crate::board::rule::Rule;
+
+/// The folder the `.rule` files are read from, a rule table is only looked for in it
+pub const RULES_FOLDER: &str = "rules";
+
+/// Is this the name of a rule table, letters, digits, `-` and `_`, like `LifeTable` or `Langtons-Loops`
+pub fn is_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// The neighbours of a rule table, in the order of Golly's transitions, clockwise from the north
+const MOORE: [(i32, i32); 8] = [(0, -1), (1, -1), (1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1)];
+const VON_NEUMANN: [(i32, i32); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+/// The NE and SW corners are not neighbours, like in the `H` rules
+const HEXAGONAL: [(i32, i32); 6] = [(0, -1), (1, 0), (1, 1), (0, 1), (-1, 0), (-1, -1)];
+const ONE_DIMENSIONAL: [(i32, i32); 2] = [(-1, 0), (1, 0)];
+
+/// A set of states, the bit s is set when the state s is in it
+type States = [u64; 4];
+
+#[inline]
+fn has_state(states: &States, state: u8) -> bool {
+    states[state as usize / 64] & (1 << (state % 64)) != 0
+}
+
+fn single_state(state: u8) -> States {
+    let mut states = [0; 4];
+    states[state as usize / 64] |= 1 << (state % 64);
+    states
+}
+
+fn state_values(states: &States) -> Vec<u8> {
+    (0..=255u8).filter(|&state| has_state(states, state)).collect()
+}
+
+/// A part of a transition, before the variables are expanded
+/// <p> - Variable: a variable declared with `var`, all its places in a transition have the same state </p>
+/// <p> - States: a state, or an inline set of states like `{1,2}` </p>
+#[derive(Clone, PartialEq, Debug)]
+enum Token {
+    Variable(String),
+    States(States),
+}
+
+/// Define a rule read from the `@TABLE` of a Golly `.rule` file, like WireWorld or Langton's Loops
+/// Each transition gives the next state of a cell from its state and the states of its neighbours,
+/// the first one that matches is used, and a cell that matches none keeps its state.
+/// <p> - name: the name after `@RULE`, used to write the rule </p>
+/// <p> - states: the number of states, the state 0 is the empty one </p>
+/// <p> - neighbours: the positions of the neighbours, in the order of the transitions </p>
+/// <p> - matches: for each place of a transition, the centre then the neighbours, and each state,
+///  the transitions that accept the state there as bits, so the first transition of a cell is found
+///  by a `and` of the places and its lowest bit </p>
+/// <p> - colors: the colours of the states from `@COLORS` </p>
+#[derive(Clone, Eq, PartialEq, Hash)]
+pub struct RuleTable {
+    name: String,
+    states: u8,
+    neighbours: &'static [(i32, i32)],
+    outputs: Vec<u8>,
+    words: usize,
+    matches: Vec<u64>,
+    colors: Vec<Option<(u8, u8, u8)>>,
+}
+
+impl fmt::Debug for RuleTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "RuleTable({}, {} states, {} transitions)", self.name, self.states, self.outputs.len())
+    }
+}
+
+/// Get the ways to reorder the neighbours of a symmetry, as the neighbour put at each place
+/// The neighbours are in a circle, turned by `step` places, and mirrored when `mirror`.
+fn rotations(size: usize, step: usize, mirror: bool) -> Vec<Vec<usize>> {
+    let mirrors: &[bool] = if mirror { &[false, true] } else { &[false] };
+
+    mirrors.iter().flat_map(|&mirrored| (0..size).step_by(step).map(move |shift| {
+        (0..size).map(|i| {
+            let i = if mirrored { (size - i) % size } else { i };
+            (i + shift) % size
+        }).collect()
+    })).collect()
+}
+
+/// Get all the different orders of the neighbours, for the `permute` symmetry
+fn permutations(mut neighbours: Vec<States>) -> Vec<Vec<States>> {
+    neighbours.sort_unstable();
+    let mut res = vec![neighbours.clone()];
+
+    // The next permutation in lexicographic order, until the last one
+    loop {
+        let pivot = match (1..neighbours.len()).rev().find(|&i| neighbours[i - 1] < neighbours[i]) {
+            Some(i) => i - 1,
+            None => return res,
+        };
+        let successor = (pivot + 1..neighbours.len()).rev().find(|&i| neighbours[i] > neighbours[pivot]).unwrap();
+        neighbours.swap(pivot, successor);
+        neighbours[pivot + 1..].reverse();
+        res.push(neighbours.clone());
+    }
+}
+
+/// Read a state, a variable or an inline set of states like `{1,2,a}`
+fn read_token(token: &str, variables: &HashMap<String, States>, states: u8) -> Result<Token, String> {
+    let token = token.trim();
+
+    if let Some(set) = token.strip_prefix('{').and_then(|set| set.strip_suffix('}')) {
+        let mut res = [0; 4];
+        for token in set.split(',') {
+            match read_token(token, variables, states)? {
+                Token::States(set) => res.iter_mut().zip(set.iter()).for_each(|(res, set)| *res |= set),
+                Token::Variable(name) => res.iter_mut().zip(variables[&name].iter()).for_each(|(res, set)| *res |= set),
+            }
+        }
+        return Ok(Token::States(res));
+    }
+    match token.parse::<u8>() {
+        Ok(state) if state < states => Ok(Token::States(single_state(state))),
+        Ok(state) => Err(format!("Invalid state '{}' in rule table, there are {} states", state, states)),
+        Err(_) if variables.contains_key(token) => Ok(Token::Variable(token.to_string())),
+        Err(_) => Err(format!("Unknown variable '{}' in rule table", token)),
+    }
+}
+
+/// Split a transition in its parts, at the commas outside of the inline sets
+/// Without commas, each character is a part, like `0123` for `0,1,2,3`.
+fn split_transition(line: &str) -> Vec<&str> {
+    if !line.contains(',') {
+        return line.char_indices().filter(|(_, c)| !c.is_whitespace()).map(|(i, c)| &line[i..i + c.len_utf8()]).collect();
+    }
+    let mut parts = vec![];
+    let (mut depth, mut start) = (0, 0);
+    for (i, c) in line.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(&line[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&line[start..]);
+    parts
+}
+
+/// Read a `@COLORS` line, `state r g b` or `first last r g b r g b` for a ramp between two colours
+fn read_colors(line: &str, colors: &mut [Option<(u8, u8, u8)>]) -> Result<(), String> {
+    let values: Vec<u8> = line.split_whitespace().map(|value| value.parse().ok()).collect::<Option<_>>()
+        .ok_or_else(|| format!("Invalid colour '{}' in rule, expected like 1 255 0 0", line))?;
+    let (first, last, from, to) = match *values.as_slice() {
+        [state, r, g, b] => (state, state, (r, g, b), (r, g, b)),
+        [first, last, r1, g1, b1, r2, g2, b2] if first <= last => (first, last, (r1, g1, b1), (r2, g2, b2)),
+        _ => return Err(format!("Invalid colour '{}' in rule, expected like 1 255 0 0", line)),
+    };
+    let mix = |from: u8, to: u8, ratio: f32| (from as f32 + (to as f32 - from as f32) * ratio) as u8;
+
+    for state in first..=last {
+        let ratio = if last > first { (state - first) as f32 / (last - first) as f32 } else { 0.0 };
+        if let Some(color) = colors.get_mut(state as usize) {
+            *color = Some((mix(from.0, to.0, ratio), mix(from.1, to.1, ratio), mix(from.2, to.2, ratio)));
+        }
+    }
+    Ok(())
+}
+
+impl RuleTable {
+    /// Read the text of a `.rule` file, its `@TABLE` and `@COLORS` sections
+    /// The table starts with `n_states:`, `neighborhood:` and `symmetries:`, then the variables like
+    /// `var a={0,1,2}` and the transitions like `0,a,1,0,0,2`: the state of the cell, of its neighbours, and the next one.
+    /// <p> - neighborhood: Moore, vonNeumann, hexagonal or oneDimensional </p>
+    /// <p> - symmetries: none, rotate4, rotate8, rotate4reflect, rotate8reflect, reflect_horizontal, permute,
+    ///  and rotate2, rotate3, rotate6, rotate6reflect for hexagonal or reflect for oneDimensional </p>
+    pub fn parse(text: &str, default_name: &str) -> Result<Self, String> {
+        let mut section = "";
+        let mut name = default_name.to_string();
+        let mut table = vec![];
+        let mut colors = vec![];
+
+        for line in text.lines() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            if line.starts_with('@') {
+                let mut words = line.split_whitespace();
+                section = words.next().unwrap();
+                if section == "@RULE" {
+                    name = words.next().unwrap_or(default_name).to_string();
+                }
+                continue;
+            }
+            match section {
+                "@TABLE" => table.push(line),
+                "@COLORS" => colors.push(line),
+                _ => {}
+            }
+        }
+        if table.is_empty() {
+            return Err(format!("No @TABLE in the rule '{}', only the rule tables are supported", name));
+        }
+        let mut rule = RuleTable::parse_table(&table, name)?;
+        colors.iter().try_for_each(|line| read_colors(line, &mut rule.colors))?;
+        Ok(rule)
+    }
+
+    /// Read the lines of a `@TABLE` section, without the comments
+    fn parse_table(lines: &[&str], name: String) -> Result<Self, String> {
+        let (mut states, mut neighbours, mut symmetries): (u16, &[(i32, i32)], &str) = (0, &MOORE, "none");
+        let mut variables: HashMap<String, States> = HashMap::new();
+        let mut transitions: Vec<(Vec<States>, u8)> = vec![];
+
+        for line in lines {
+            if let Some((key, value)) = line.split_once(':') {
+                match key.trim() {
+                    "n_states" => states = value.trim().parse().ok().filter(|states| (2..=255).contains(states))
+                        .ok_or_else(|| format!("Invalid number of states '{}' in rule table, expected 2 to 255", value.trim()))?,
+                    "neighborhood" => neighbours = match value.trim() {
+                        "Moore" => &MOORE,
+                        "vonNeumann" => &VON_NEUMANN,
+                        "hexagonal" => &HEXAGONAL,
+                        "oneDimensional" => &ONE_DIMENSIONAL,
+                        other => return Err(format!("Unknown neighborhood '{}' in rule table", other)),
+                    },
+                    "symmetries" => symmetries = value.trim(),
+                    other => return Err(format!("Unknown setting '{}' in rule table", other)),
+                }
+                continue;
+            }
+            if states == 0 {
+                return Err(String::from("The rule table must start with n_states"));
+            }
+            if let Some(variable) = line.strip_prefix("var ") {
+                let (variable, set) = variable.split_once('=')
+                    .ok_or_else(|| format!("Invalid variable '{}' in rule table, expected like var a={{0,1}}", line))?;
+                let set = match read_token(set, &variables, states as u8)? {
+                    Token::States(set) => set,
+                    Token::Variable(name) => variables[&name],
+                };
+                variables.insert(variable.trim().to_string(), set);
+                continue;
+            }
+            transitions.extend(RuleTable::expand(line, &variables, states as u8, neighbours, symmetries)?);
+        }
+        Ok(RuleTable::from_transitions(name, states as u8, neighbours, &transitions))
+    }
+
+    /// Read a transition, and get it for each state of its variables used more than once,
+    /// turned and mirrored by the symmetries
+    fn expand(line: &str, variables: &HashMap<String, States>, states: u8, neighbours: &[(i32, i32)],
+              symmetries: &str) -> Result<Vec<(Vec<States>, u8)>, String> {
+        let tokens = split_transition(line).iter()
+            .map(|token| read_token(token, variables, states))
+            .collect::<Result<Vec<_>, _>>()?;
+        if tokens.len() != neighbours.len() + 2 {
+            return Err(format!("Invalid transition '{}' in rule table, expected {} states", line, neighbours.len() + 2));
+        }
+        let size = neighbours.len();
+        let orders = match (size, symmetries) {
+            (_, "none") => Some(rotations(size, size, false)),
+            (_, "permute") => None,
+            (8, "rotate4") | (6, "rotate3") => Some(rotations(size, 2, false)),
+            (8, "rotate8") | (4, "rotate4") | (6, "rotate6") | (2, "reflect") => Some(rotations(size, 1, false)),
+            (8, "rotate4reflect") => Some(rotations(size, 2, true)),
+            (8, "rotate8reflect") | (4, "rotate4reflect") | (6, "rotate6reflect") => Some(rotations(size, 1, true)),
+            (8, "reflect_horizontal") | (4, "reflect_horizontal") => Some(rotations(size, size, true)),
+            (6, "rotate2") => Some(rotations(size, 3, false)),
+            _ => return Err(format!("Unknown symmetries '{}' for this neighborhood in rule table", symmetries)),
+        };
+
+        // The variables used more than once are bound, they take each of their states in turn
+        let mut bound: Vec<&str> = vec![];
+        for token in &tokens {
+            if let Token::Variable(name) = token {
+                let count = tokens.iter().filter(|&other| other == token).count();
+                if count > 1 && !bound.contains(&name.as_str()) {
+                    bound.push(name);
+                }
+            }
+        }
+        let mut values: Vec<Vec<(&str, u8)>> = vec![vec![]];
+        for &name in &bound {
+            values = values.into_iter()
+                .flat_map(|value| state_values(&variables[name]).into_iter().map(move |state| {
+                    let mut value = value.clone();
+                    value.push((name, state));
+                    value
+                }))
+                .collect();
+        }
+
+        let mut res = vec![];
+        for value in values {
+            let states_of = |token: &Token| match token {
+                Token::States(set) => *set,
+                Token::Variable(name) => match value.iter().find(|(bound, _)| bound == name) {
+                    Some(&(_, state)) => single_state(state),
+                    None => variables[name],
+                },
+            };
+            let inputs: Vec<States> = tokens[..=size].iter().map(states_of).collect();
+            let output = match state_values(&states_of(&tokens[size + 1])).as_slice() {
+                &[output] => output,
+                _ => return Err(format!("Invalid transition '{}' in rule table, the next state must be a state or a variable used before", line)),
+            };
+            let orders: Vec<Vec<States>> = match &orders {
+                Some(orders) => orders.iter().map(|order| order.iter().map(|&i| inputs[1 + i]).collect()).collect(),
+                None => permutations(inputs[1..].to_vec()),
+            };
+            let mut seen = HashSet::new();
+            for order in orders {
+                if seen.insert(order.clone()) {
+                    res.push((std::iter::once(inputs[0]).chain(order).collect(), output));
+                }
+            }
+        }
+        Ok(res)
+    }
+
+    /// Index the transitions by the states each place accepts
+    fn from_transitions(name: String, states: u8, neighbours: &'static [(i32, i32)], transitions: &[(Vec<States>, u8)]) -> Self {
+        let words = transitions.len().div_ceil(64).max(1);
+        let mut matches = vec![0; (neighbours.len() + 1) * states as usize * words];
+
+        for (index, (inputs, _)) in transitions.iter().enumerate() {
+            for (place, input) in inputs.iter().enumerate() {
+                for state in (0..states).filter(|&state| has_state(input, state)) {
+                    matches[(place * states as usize + state as usize) * words + index / 64] |= 1 << (index % 64);
+                }
+            }
+        }
+        RuleTable {
+            name,
+            states,
+            neighbours,
+            outputs: transitions.iter().map(|&(_, output)| output).collect(),
+            words,
+            matches,
+            colors: vec![None; states as usize],
+        }
+    }
+
+    /// Read the rule file `name.rule` of `RULES_FOLDER`
+    /// Only a name is taken, not a path, so a pattern file can't make it read another file.
+    pub fn load(name: &str) -> Result<Self, String> {
+        if !is_name(name) {
+            return Err(format!("Invalid rule table name '{}', expected the name of a .rule file in {}", name, RULES_FOLDER));
+        }
+        let path = Path::new(RULES_FOLDER).join(format!("{}.rule", name));
+        if !path.is_file() {
+            return Err(format!("Unknown rule '{}', no {}", name, path.display()));
+        }
+        let text = fs::read_to_string(&path).map_err(|err| format!("Can't read the rule {}: {}", path.display(), err))?;
+
+        RuleTable::parse(&text, name)
+    }
+
+    #[inline]
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    #[inline]
+    pub fn get_states(&self) -> u8 {
+        self.states
+    }
+
+    /// Get the number of transitions, once the variables and the symmetries are expanded
+    #[inline]
+    pub fn get_transitions(&self) -> usize {
+        self.outputs.len()
+    }
+
+    /// Get the adjacent cells the table looks at
+    pub fn get_neighbourhood(&self) -> Neighbourhood {
+        match self.neighbours.len() {
+            8 => Neighbourhood::Moore,
+            6 => Neighbourhood::Hexagonal,
+            4 => Neighbourhood::VonNeumann,
+            // The west and east cells
+            _ => Neighbourhood::Custom(0x18),
+        }
+    }
+
+    /// Get the colour of a state from `@COLORS`, if the rule has one
+    pub fn get_color(&self, state: u8) -> Option<(u8, u8, u8)> {
+        self.colors.get(state as usize).copied().flatten()
+    }
+
+    /// Give the next state of a cell from its state and the states of its neighbours, in the order of the table
+    pub fn apply(&self, state: u8, neighbours: &[u8]) -> u8 {
+        let (states, words) = (self.states as usize, self.words);
+
+        for word in 0..words {
+            let mut transitions = self.matches[state as usize * words + word];
+            for (place, &neighbour) in neighbours.iter().enumerate() {
+                if transitions == 0 {
+                    break;
+                }
+                transitions &= self.matches[((place + 1) * states + neighbour as usize) * words + word];
+            }
+            if transitions != 0 {
+                return self.outputs[word * 64 + transitions.trailing_zeros() as usize];
+            }
+        }
+        state
+    }
+}
+
+/// Compute the next generation of a board with a rule table
+/// The cells with a state and their neighbours are looked at, an empty cell with empty neighbours stays empty.
+pub(crate) fn next(cells: &HashSet<Cell>, rule: &Rule, table: &RuleTable, parallel: bool) -> HashSet<Cell> {
+    let topology = rule.get_topology();
+    let states: HashMap<(i32, i32), u8> = cells.iter().map(|cell| ((cell.x, cell.y), cell.status.get_state())).collect();
+    let state = |x: i32, y: i32| topology.wrap(x, y).and_then(|pos| states.get(&pos).copied()).unwrap_or(0);
+    let interest: HashSet<(i32, i32)> = cells.iter()
+        .flat_map(|cell| table.neighbours.iter().filter_map(move |(dx, dy)| topology.wrap(cell.x - dx, cell.y - dy)))
+        .chain(states.keys().copied())
+        .collect();
+    let next = |&(x, y): &(i32, i32)| {
+        let mut neighbours = [0; 8];
+        table.neighbours.iter().zip(neighbours.iter_mut()).for_each(|((dx, dy), neighbour)| *neighbour = state(x + dx, y + dy));
+
+        match table.apply(state(x, y), &neighbours[..table.neighbours.len()]) {
+            0 => None,
+            next => Some(Cell::new(x, y, STATUS::from_state(next))),
+        }
+    };
+
+    if parallel {
+        interest.par_iter().filter_map(next).collect()
+    } else {
+        interest.iter().filter_map(next).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::cell::fixtures::{board, positions};
+    use crate::engine::LifeEngine;
+
+    /// Get the transitions of B3/S23 for the masks of neighbours that are the first of their symmetry class,
+    /// the others come from the symmetries. A cell with 2 neighbours keeps its state with the bound variable `a`.
+    fn conway_table(symmetries: &str, step: usize, mirror: bool) -> RuleTable {
+        let turn = |mask: u32, order: &[usize]| order.iter().enumerate()
+            .fold(0, |res, (place, &i)| res | ((mask >> i) & 1) << place);
+        let orders = rotations(8, step, mirror);
+        let mut lines = vec![format!("n_states:2\nneighborhood:Moore\nsymmetries:{}", symmetries),
+                             String::from("var a={0,1}\nvar b={0,1}\nvar c={0,1}\nvar d={0,1}"),
+                             String::from("var e={0,1}\nvar f={0,1}\nvar g={0,1}\nvar h={0,1}")];
+
+        for mask in (0..256u32).filter(|mask| matches!(mask.count_ones(), 2 | 3)) {
+            if orders.iter().any(|order| turn(mask, order) < mask) {
+                continue;
+            }
+            let neighbours: Vec<String> = (0..8).map(|i| ((mask >> i) & 1).to_string()).collect();
+            match mask.count_ones() {
+                2 => lines.push(format!("a,{},a", neighbours.join(","))),
+                _ => lines.push(format!("b,{},1", neighbours.join(","))),
+            }
+        }
+        lines.push(String::from("1,a,b,c,d,e,f,g,h,0"));
+        RuleTable::parse(&format!("@RULE Conway\n@TABLE\n{}\n", lines.join("\n")), "Conway").unwrap()
+    }
+
+    /// Check that the table gives the same generations as B3/S23 on a soup
+    fn check_against_conway(table: RuleTable) {
+        let mut state: u32 = 7;
+        let soup: Vec<(i32, i32)> = (-12..12).flat_map(|y| (-12..12).map(move |x| (x, y)))
+            .filter(|_| {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                (state >> 16) & 1 == 0
+            })
+            .collect();
+        let mut expected = board(&soup, Rule::conway());
+        let mut res = board(&soup, Rule::table(table));
+
+        for generation in 1..=20 {
+            expected.step();
+            res.step();
+            assert_eq!(positions(&res), positions(&expected), "generation {}", generation);
+        }
+    }
+
+    #[test]
+    fn apply_conway_table() {
+        let table = conway_table("rotate8", 1, false);
+
+        assert_eq!(table.apply(0, &[1, 0, 1, 0, 0, 0, 1, 0]), 1);
+        assert_eq!(table.apply(0, &[1, 1, 0, 0, 0, 0, 0, 0]), 0);
+        assert_eq!(table.apply(1, &[0, 0, 0, 1, 0, 0, 0, 1]), 1);
+        assert_eq!(table.apply(1, &[0, 1, 0, 1, 0, 1, 0, 1]), 0);
+        assert_eq!(table.apply(1, &[0, 0, 0, 0, 0, 0, 0, 0]), 0);
+    }
+
+    #[test]
+    fn symmetries_expand_to_conway() {
+        for &(symmetries, step, mirror) in [("none", 8, false), ("rotate4", 2, false), ("rotate4reflect", 2, true),
+                                            ("rotate8", 1, false), ("rotate8reflect", 1, true)].iter() {
+            let table = conway_table(symmetries, step, mirror);
+            // Each mask of 2 neighbours gives a transition for each state of the bound variable
+            assert_eq!(table.get_transitions(), 2 * 28 + 56 + 1, "{}", symmetries);
+            check_against_conway(table);
+        }
+    }
+
+    #[test]
+    fn permute_expands_to_conway() {
+        let text = "@TABLE\nn_states:2\nneighborhood:Moore\nsymmetries:permute\nvar a={0,1}\nvar b={0,1}\nvar c={0,1}\n\
+                    var d={0,1}\nvar e={0,1}\nvar f={0,1}\nvar g={0,1}\nvar h={0,1}\n\
+                    a,1,1,0,0,0,0,0,0,a\nb,1,1,1,0,0,0,0,0,1\n1,a,b,c,d,e,f,g,h,0\n";
+        let table = RuleTable::parse(text, "Permuted").unwrap();
+
+        assert_eq!(table.get_name(), "Permuted");
+        check_against_conway(table);
+    }
+}
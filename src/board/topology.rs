@@ -0,0 +1,177 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// Define the shape of the board, written like Golly after the rule: `B3/S23:T100,80`
+/// A bounded board covers the cells from (0, 0) to (width - 1, height - 1).
+/// A size of 0 makes that side infinite, so `:T100,0` is a cylinder.
+/// <p> - Plane: the default unbounded plane </p>
+/// <p> - BoundedPlane `:P`: the cells outside the board are always dead </p>
+/// <p> - Torus `:T`: the opposite edges are joined </p>
+/// <p> - KleinBottle `:K`: like a torus, but one pair of edges is joined with a twist, marked with a `*` like Golly:
+///  `:K100*,80` twists the top and bottom edges, x is mirrored when they are crossed, `:K100,80*` the left and right ones </p>
+/// <p> - CrossSurface `:C`: both pairs of edges are joined with a twist </p>
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Hash, Default)]
+pub enum Topology {
+    #[default]
+    Plane,
+    BoundedPlane(u32, u32),
+    Torus(u32, u32),
+    KleinBottle(u32, u32, bool),
+    CrossSurface(u32, u32),
+}
+
+/// Get the position on a side of size `size` and the number of times an edge was crossed
+#[inline]
+fn wrap_axis(pos: i32, size: u32) -> (i32, i32) {
+    if size == 0 {
+        (pos, 0)
+    } else {
+        (pos.rem_euclid(size as i32), pos.div_euclid(size as i32))
+    }
+}
+
+/// Mirror the position on a side when the other side edge was crossed an odd number of times
+#[inline]
+fn twist(pos: i32, size: u32, crossed: i32) -> i32 {
+    if crossed % 2 != 0 { size as i32 - 1 - pos } else { pos }
+}
+
+impl Topology {
+    /// Get the size of the board, None for the unbounded plane
+    pub fn get_size(&self) -> Option<(u32, u32)> {
+        match *self {
+            Topology::Plane => None,
+            Topology::BoundedPlane(w, h) | Topology::Torus(w, h)
+            | Topology::KleinBottle(w, h, _) | Topology::CrossSurface(w, h) => Some((w, h)),
+        }
+    }
+
+    /// Get the position of a cell on the board, following the joined edges
+    /// None if the position is outside a bounded plane
+    pub fn wrap(&self, x: i32, y: i32) -> Option<(i32, i32)> {
+        match *self {
+            Topology::Plane => Some((x, y)),
+            Topology::BoundedPlane(w, h) => {
+                let inside = |pos: i32, size: u32| size == 0 || (pos >= 0 && pos < size as i32);
+                if inside(x, w) && inside(y, h) { Some((x, y)) } else { None }
+            }
+            Topology::Torus(w, h) => Some((wrap_axis(x, w).0, wrap_axis(y, h).0)),
+            Topology::KleinBottle(w, h, twisted_x) => {
+                let (x, crossed_x) = wrap_axis(x, w);
+                let (y, crossed_y) = wrap_axis(y, h);
+                if twisted_x {
+                    Some((twist(x, w, crossed_y), y))
+                } else {
+                    Some((x, twist(y, h, crossed_x)))
+                }
+            }
+            Topology::CrossSurface(w, h) => {
+                let (x, crossed_x) = wrap_axis(x, w);
+                let (y, crossed_y) = wrap_axis(y, h);
+                Some((twist(x, w, crossed_y), twist(y, h, crossed_x)))
+            }
+        }
+    }
+}
+
+/// Read the Golly notation without the colon, like `T100,80` or `K100*,80`
+impl FromStr for Topology {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let invalid = || format!("Invalid topology '{}', expected like T100,80", s);
+        let kind = s.chars().next().ok_or_else(invalid)?;
+        let mut sizes = s[kind.len_utf8()..].split(',');
+
+        let width = sizes.next().ok_or_else(invalid)?.trim();
+        let height = sizes.next().unwrap_or(width).trim();
+        if sizes.next().is_some() {
+            return Err(invalid());
+        }
+        let twisted_x = width.ends_with('*');
+        let twisted_y = height.ends_with('*');
+        let parse = |size: &str| size.trim_end_matches('*').parse::<u32>().map_err(|_| invalid());
+        let (w, h) = (parse(width)?, parse(height)?);
+
+        match kind.to_ascii_uppercase() {
+            'P' if w == 0 && h == 0 => Ok(Topology::Plane),
+            'P' => Ok(Topology::BoundedPlane(w, h)),
+            'T' => Ok(Topology::Torus(w, h)),
+            'K' if twisted_x != twisted_y && w > 0 && h > 0 => Ok(Topology::KleinBottle(w, h, twisted_x)),
+            'K' => Err(format!("Invalid Klein bottle '{}', one side must be twisted with a *, like K100*,80", s)),
+            'C' if w > 0 && h > 0 => Ok(Topology::CrossSurface(w, h)),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl fmt::Display for Topology {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Topology::Plane => Ok(()),
+            Topology::BoundedPlane(w, h) => write!(f, ":P{},{}", w, h),
+            Topology::Torus(w, h) => write!(f, ":T{},{}", w, h),
+            Topology::KleinBottle(w, h, true) => write!(f, ":K{}*,{}", w, h),
+            Topology::KleinBottle(w, h, false) => write!(f, ":K{},{}*", w, h),
+            Topology::CrossSurface(w, h) => write!(f, ":C{},{}", w, h),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::cell::fixtures::{board, positions};
+    use crate::engine::LifeEngine;
+
+    #[test]
+    fn glider_wraps_around_a_torus() {
+        let glider = [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)];
+        let mut board = board(&glider, "B3/S23:T8,8".parse().unwrap());
+        let moved = |shift: i32| {
+            let mut cells: Vec<_> = glider.iter().map(|&(x, y)| ((x + shift) % 8, (y + shift) % 8)).collect();
+            cells.sort_unstable();
+            cells
+        };
+
+        // The glider goes a cell down and right every 4 generations, across the edges
+        board.step_n(24);
+        assert_eq!(positions(&board), moved(6));
+        board.step_n(8);
+        assert_eq!(positions(&board), moved(0));
+    }
+
+    #[test]
+    fn klein_bottle_mirrors_the_twisted_edges() {
+        let top_bottom: Topology = "K8*,6".parse().unwrap();
+        let left_right: Topology = "K8,6*".parse().unwrap();
+
+        assert_eq!(top_bottom.wrap(3, -1), Some((4, 5)));
+        assert_eq!(top_bottom.wrap(3, 6), Some((4, 0)));
+        assert_eq!(top_bottom.wrap(-1, 2), Some((7, 2)));
+        assert_eq!(left_right.wrap(-1, 2), Some((7, 3)));
+        assert_eq!(left_right.wrap(8, 0), Some((0, 5)));
+        assert_eq!(left_right.wrap(3, -1), Some((3, 5)));
+    }
+
+    #[test]
+    fn blinker_across_a_twisted_edge() {
+        let mut board = board(&[(1, 0), (2, 0), (3, 0)], "B3/S23:K8*,6".parse().unwrap());
+
+        board.step();
+        assert_eq!(positions(&board), vec![(2, 0), (2, 1), (5, 5)]);
+        board.step();
+        assert_eq!(positions(&board), vec![(1, 0), (2, 0), (3, 0)]);
+    }
+
+    #[test]
+    fn cross_surface_mirrors_both_edges() {
+        let cross: Topology = "C8,6".parse().unwrap();
+
+        assert_eq!(cross.wrap(-1, 2), Some((7, 3)));
+        assert_eq!(cross.wrap(3, -1), Some((4, 5)));
+        assert_eq!(cross.wrap(-1, -1), Some((0, 0)));
+        assert_eq!(cross.wrap(8, 6), Some((7, 5)));
+    }
+}
@@ -0,0 +1,111 @@
+use std::collections::HashSet;
+
+use rayon::prelude::*;
+
+use crate::board::cell::{Cell, STATUS};
+use crate::board::rule::Rule;
+use crate::board::ADJ_POSITIONS;
+
+/// The name of the rule, read in any case
+pub const NAME: &str = "WireWorld";
+
+/// The states of WireWorld, numbered like Golly and MCell
+/// <p> - EMPTY: nothing, it never changes </p>
+/// <p> - HEAD: the head of an electron, it becomes a tail </p>
+/// <p> - TAIL: the tail of an electron, it becomes a conductor </p>
+/// <p> - CONDUCTOR: a wire, it becomes a head when 1 or 2 of its 8 neighbours are heads </p>
+pub const EMPTY: u8 = 0;
+pub const HEAD: u8 = 1;
+pub const TAIL: u8 = 2;
+pub const CONDUCTOR: u8 = 3;
+pub const STATES: u8 = 4;
+
+/// The colours of the states, like Golly
+pub const COLORS: [(u8, u8, u8); STATES as usize] = [(48, 48, 48), (0, 128, 255), (255, 255, 255), (255, 128, 0)];
+
+/// Give the next state of a cell from its state and its number of neighbours that are heads
+#[inline]
+pub fn next_state(state: u8, heads: usize) -> u8 {
+    match state {
+        HEAD => TAIL,
+        TAIL => CONDUCTOR,
+        CONDUCTOR if heads == 1 || heads == 2 => HEAD,
+        state => state,
+    }
+}
+
+/// Compute the next generation of a board with WireWorld
+/// An empty cell stays empty, so only the cells with a state are looked at, and the heads are counted around the conductors.
+/// The heads are the state 1, stored as the living cells.
+pub(crate) fn next(cells: &HashSet<Cell>, rule: &Rule, parallel: bool) -> HashSet<Cell> {
+    let topology = rule.get_topology();
+    let next = |cell: &Cell| {
+        let state = cell.status.get_state();
+        let heads = if state == CONDUCTOR {
+            ADJ_POSITIONS.iter()
+                .filter_map(|(dx, dy)| topology.wrap(cell.x + dx, cell.y + dy))
+                .filter(|&(x, y)| cells.contains(&Cell::new(x, y, STATUS::ALIVE)))
+                .count()
+        } else {
+            0
+        };
+
+        Cell::new(cell.x, cell.y, STATUS::from_state(next_state(state, heads)))
+    };
+
+    if parallel {
+        cells.par_iter().map(next).collect()
+    } else {
+        cells.iter().map(next).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+    use crate::board::cell::fixtures::sorted_states;
+
+    /// Get a board of WireWorld from the states of its cells
+    fn board(states: &[(i32, i32, u8)]) -> Board {
+        let cells: Vec<Cell> = states.iter().map(|&(x, y, state)| Cell::new(x, y, STATUS::from_state(state))).collect();
+
+        Board::new(&cells, Rule::wireworld())
+    }
+
+    #[test]
+    fn electron_moves_along_a_wire() {
+        let mut board = board(&[(0, 0, TAIL), (1, 0, HEAD), (2, 0, CONDUCTOR), (3, 0, CONDUCTOR), (4, 0, CONDUCTOR)]);
+
+        board.next();
+        assert_eq!(sorted_states(board.get_leaving_cells()),
+                   vec![(0, 0, CONDUCTOR), (1, 0, TAIL), (2, 0, HEAD), (3, 0, CONDUCTOR), (4, 0, CONDUCTOR)]);
+        board.next();
+        assert_eq!(sorted_states(board.get_leaving_cells()),
+                   vec![(0, 0, CONDUCTOR), (1, 0, CONDUCTOR), (2, 0, TAIL), (3, 0, HEAD), (4, 0, CONDUCTOR)]);
+        board.next();
+        board.next();
+        assert_eq!(sorted_states(board.get_leaving_cells()),
+                   vec![(0, 0, CONDUCTOR), (1, 0, CONDUCTOR), (2, 0, CONDUCTOR), (3, 0, CONDUCTOR), (4, 0, TAIL)]);
+        board.next();
+        assert!(sorted_states(board.get_leaving_cells()).iter().all(|&(_, _, state)| state == CONDUCTOR));
+    }
+
+    #[test]
+    fn three_heads_leave_a_conductor() {
+        let mut board = board(&[(-1, -1, HEAD), (0, -1, HEAD), (1, -1, HEAD), (0, 0, CONDUCTOR), (3, 0, CONDUCTOR)]);
+        board.next();
+
+        assert_eq!(sorted_states(board.get_leaving_cells()),
+                   vec![(-1, -1, TAIL), (0, -1, TAIL), (1, -1, TAIL), (0, 0, CONDUCTOR), (3, 0, CONDUCTOR)]);
+    }
+
+    #[test]
+    fn next_state_of_each_state() {
+        assert_eq!(next_state(EMPTY, 2), EMPTY);
+        assert_eq!(next_state(HEAD, 0), TAIL);
+        assert_eq!(next_state(TAIL, 1), CONDUCTOR);
+        assert_eq!((0..=8).map(|heads| next_state(CONDUCTOR, heads)).collect::<Vec<_>>(),
+                   vec![CONDUCTOR, HEAD, HEAD, CONDUCTOR, CONDUCTOR, CONDUCTOR, CONDUCTOR, CONDUCTOR, CONDUCTOR]);
+    }
+}
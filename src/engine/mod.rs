@@ -0,0 +1,105 @@
+use crate::board::Board;
+use crate::board::cell::{Cell, STATUS};
+use crate::board::history::HistoryDepth;
+use crate::board::rule::Rule;
+use crate::pattern::PatternMetadata;
+use std::str::FromStr;
+
+/// The simulation backends that can be chosen
+/// <p> - Board: the sparse board, one generation per step </p>
+/// <p> - Parallel: the sparse board, each generation is computed by row bands on all the threads </p>
+/// <p> - Tiles: 64x64 tiles of bits, the neighbours are counted with bitwise adders </p>
+/// <p> - HashLife: the quadtree, each step can be 2^n generations </p>
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Default)]
+pub enum EngineKind {
+    #[default]
+    Board,
+    Parallel,
+    Tiles,
+    HashLife,
+}
+
+/// Read the name of an engine, like `board` or `hashlife`
+impl FromStr for EngineKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "board" => Ok(EngineKind::Board),
+            "parallel" => Ok(EngineKind::Parallel),
+            "tiles" => Ok(EngineKind::Tiles),
+            "hashlife" => Ok(EngineKind::HashLife),
+            other => Err(format!("Unknown engine '{}', expected board, parallel, tiles or hashlife", other)),
+        }
+    }
+}
+
+/// A simulation backend
+/// The graphic interface and the save functions only know this trait,
+/// so a new engine can be dropped in without touching them.
+pub trait LifeEngine {
+    /// Advance one step of the engine, it can be more than one generation
+    fn step(&mut self);
+
+    /// Advance n generations
+    fn step_n(&mut self, n: u64);
+
+    /// Go back to the previous step, if the engine still has it
+    fn prev(&mut self);
+
+    /// Go to a generation, forward or backward
+    /// False when the generation is before the one the engine was created at,
+    /// or before the history once a cell was set by hand, as stepping from the initial cells would lose the edits
+    fn goto(&mut self, generation: u64) -> bool;
+
+    /// Change how many steps `prev` can undo
+    fn set_history_depth(&mut self, depth: HistoryDepth);
+
+    fn get_cell(&self, x: i32, y: i32) -> Cell;
+
+    fn set_cell(&mut self, x: i32, y: i32, status: STATUS) -> Cell;
+
+    fn inverse_cell(&mut self, x: i32, y: i32) -> Cell {
+        let status = self.get_cell(x, y).status.inverse();
+
+        self.set_cell(x, y, status)
+    }
+
+    /// Give a cell the next state of the rule, the way the cells are painted
+    fn cycle_cell(&mut self, x: i32, y: i32) -> Cell {
+        let status = self.get_cell(x, y).status.cycle(self.get_rule().get_states());
+
+        self.set_cell(x, y, status)
+    }
+
+    /// Get the living cells inside the rectangle (min_x, min_y, max_x, max_y)
+    fn get_cells_in(&self, rect: (i32, i32, i32, i32)) -> Vec<Cell>;
+
+    /// Get the living cells the engine was created with
+    fn get_initial_cells(&self) -> Vec<Cell>;
+
+    fn get_population(&self) -> u64;
+
+    /// Get the smallest rectangle containing the living cells, as (min_x, min_y, max_x, max_y)
+    fn get_bounding_box(&self) -> Option<(i32, i32, i32, i32)>;
+
+    fn get_generation(&self) -> u64;
+
+    fn get_rule(&self) -> &Rule;
+
+    /// Get the name, author and comments of the pattern, as loaded
+    fn get_metadata(&self) -> &PatternMetadata;
+
+    /// Get the seed the births and survivals of a stochastic rule are drawn from, None for the other rules
+    fn get_seed(&self) -> Option<u64> {
+        None
+    }
+
+    /// Get a board at the current generation, with the living cells and the seed, to run it apart from the engine
+    fn to_board(&self) -> Board {
+        let cells = self.get_bounding_box().map_or(vec![], |rect| self.get_cells_in(rect));
+        let metadata = PatternMetadata { generation: self.get_generation(), ..self.get_metadata().clone() };
+
+        Board::new(&cells, self.get_rule().clone()).with_metadata(metadata).with_seed(self.get_seed().unwrap_or_default())
+    }
+}
@@ -1,6 +1,5 @@
 use ggez::mint::Point2;
 use crate::board::cell::Cell;
-use std::collections::HashSet;
 
 #[derive(Clone, PartialEq, Debug)]
 pub struct Pixel {
@@ -21,6 +20,8 @@ impl Pixel {
 
 /// This struct is here to regroup the logic about what part
 /// of the board is show on screen.
+/// With a hexagonal rule, each row is drawn half a cell to the left of the one above it,
+/// so the 6 neighbours of a cell are around it.
 #[derive(Clone, PartialEq, Debug)]
 pub struct Camera {
     position_on_board_pixel: Point2<f32>,
@@ -29,6 +30,7 @@ pub struct Camera {
     cell_size: f32,
     screen_size: Point2<f32>,
     zoom_ratio: f32,
+    hexagonal: bool,
     cells_pos: Vec<Pixel>
 }
 
@@ -36,7 +38,7 @@ impl Camera {
     pub fn new(position_on_board: Point2<f32>, screen_size: Point2<f32>) -> Self {
         let cell_size = 16.0;
         let position_on_board_pixel = Point2{x: position_on_board.x * cell_size, y: position_on_board.y * cell_size };
-        let position_on_board_end = Point2{x: position_on_board.x + (screen_size.x / cell_size) as f32, y: position_on_board.y + (screen_size.y / cell_size) };
+        let position_on_board_end = Point2{x: position_on_board.x + screen_size.x / cell_size, y: position_on_board.y + (screen_size.y / cell_size) };
 
         let mut c = Self {
             position_on_board,
@@ -44,6 +46,7 @@ impl Camera {
             position_on_board_pixel,
             screen_size,
             zoom_ratio: 1.0,
+            hexagonal: false,
             cell_size,
             cells_pos: vec![]
         };
@@ -52,10 +55,24 @@ impl Camera {
         c
     }
 
+    /// Draw the rows offset like a hexagonal grid, or not
+    pub fn set_hexagonal(&mut self, hexagonal: bool) {
+        self.hexagonal = hexagonal;
+    }
+
+    pub fn is_hexagonal(&self) -> bool {
+        self.hexagonal
+    }
+
+    /// Get how far a row is moved to the right, in cells
+    fn row_shift(&self, y: f32) -> f32 {
+        if self.hexagonal { -y / 2.0 } else { 0.0 }
+    }
+
     /// Set the position of cells to show on screen
     /// It's used on draw living cells and draw the board lines
-    pub fn active_cells_to_show(&self, cells: &Box<HashSet<Cell>>) -> Vec<Pixel> {
-        cells.iter().map(|cell| (cell.x as f32, cell.y as f32)).map(|(x, y)| (
+    pub fn active_cells_to_show(&self, cells: &[Cell]) -> Vec<Pixel> {
+        cells.iter().map(|cell| (cell.x as f32 + self.row_shift(cell.y as f32), cell.y as f32)).map(|(x, y)| (
             (x * self.get_cell_size(), y * self.get_cell_size()),
             (x * self.get_cell_size() - self.position_on_board_pixel.x, y * self.get_cell_size() - self.position_on_board_pixel.y),
             (x, y),
@@ -66,13 +83,26 @@ impl Camera {
             .collect()
     }
 
+    /// Get the part of the board shown on screen, as (min_x, min_y, max_x, max_y)
+    /// The offset rows of a hexagonal grid show more columns.
+    pub fn get_visible_rect(&self) -> (i32, i32, i32, i32) {
+        let min_y = self.position_on_board.y.floor();
+        let max_y = (self.position_on_board.y + self.screen_size.y / self.get_cell_size()).ceil();
+        let (shift_top, shift_bottom) = (self.row_shift(min_y), self.row_shift(max_y));
+
+        ((self.position_on_board.x - shift_top.max(shift_bottom)).floor() as i32,
+         min_y as i32,
+         (self.position_on_board.x + self.screen_size.x / self.get_cell_size() - shift_top.min(shift_bottom)).ceil() as i32,
+         max_y as i32)
+    }
+
     pub fn line_to_show(&self) -> &Vec<Pixel> {
        &self.cells_pos
     }
 
     pub fn update_line_to_show(&mut self) {
-        let x_iterator = self.position_on_board.x as i32..(self.position_on_board.x + self.screen_size.x / self.get_cell_size()) as i32;
-        let y_iterator = self.position_on_board.y as i32..(self.position_on_board.y + self.screen_size.y / self.get_cell_size()) as i32;
+        let x_iterator = self.position_on_board.x.floor() as i32..(self.position_on_board.x + self.screen_size.x / self.get_cell_size()).ceil() as i32;
+        let y_iterator = self.position_on_board.y.floor() as i32..(self.position_on_board.y + self.screen_size.y / self.get_cell_size()).ceil() as i32;
 
         self.cells_pos = y_iterator.map(|y| (self.position_on_board.x, y as f32))
             .chain(x_iterator.map(|x| (x as f32, self.position_on_board.y)))
@@ -87,9 +117,26 @@ impl Camera {
             .collect();
     }
 
+    pub fn screen_pos_from_board_pos(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        (x * self.get_cell_size() - self.position_on_board_pixel.x,
+         y * self.get_cell_size() - self.position_on_board_pixel.y)
+    }
+
+    /// Get the edges of a bounded board of this size on screen, as (x, y, w, h) in pixels
+    /// An infinite side, of size 0, covers the whole screen
+    pub fn boundary_to_show(&self, (width, height): (u32, u32)) -> (f32, f32, f32, f32) {
+        let (x, y) = self.screen_pos_from_board_pos((0.0, 0.0));
+        let (end_x, end_y) = self.screen_pos_from_board_pos((width as f32, height as f32));
+        let (x, end_x) = if width == 0 { (-1.0, self.screen_size.x + 1.0) } else { (x, end_x) };
+        let (y, end_y) = if height == 0 { (-1.0, self.screen_size.y + 1.0) } else { (y, end_y) };
+
+        (x, y, end_x - x, end_y - y)
+    }
+
     pub fn board_pos_from_screen_pos(&self, (x, y): (f32, f32)) -> (f32, f32) {
-        ((x + self.position_on_board_pixel.x as f32) / self.get_cell_size(),
-         (y + self.position_on_board_pixel.y as f32) / self.get_cell_size())
+        let y = (y + self.position_on_board_pixel.y) / self.get_cell_size();
+
+        ((x + self.position_on_board_pixel.x) / self.get_cell_size() - self.row_shift(y.floor()), y)
     }
 
     pub fn set_zoom_ratio(&mut self, zoom_ratio: f32) -> &Self {
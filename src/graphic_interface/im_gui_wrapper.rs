@@ -11,6 +11,9 @@ use imgui_gfx_renderer::*;
 use std::time::{Instant, Duration};
 use std::ops::RangeInclusive;
 
+/// Height of the control window, at the bottom of the screen
+pub const WINDOW_HEIGHT: f32 = 125.0;
+
 /// Describe the state of the mouse on a frame
 #[derive(Copy, Clone, PartialEq, Debug, Default)]
 struct MouseState {
@@ -29,6 +32,8 @@ pub enum UiButton {
     Play,
     SaveMap,
     SaveInitMap,
+    Overwrite,
+    Analyse,
 }
 
 pub struct ImGuiWrapper {
@@ -40,7 +45,17 @@ pub struct ImGuiWrapper {
     last_button: Option<UiButton>,
 
     time_per_step: Duration,
-    zoom_ratio: f32
+    zoom_ratio: f32,
+    save_path: ImString,
+    ask_overwrite: bool,
+    message: ImString,
+    pattern_info: Vec<String>,
+
+    /// The generation shown, and the range of the timeline
+    generation: u64,
+    timeline: RangeInclusive<u64>,
+    goto_text: ImString,
+    goto: Option<u64>,
 }
 
 
@@ -64,7 +79,15 @@ impl ImGuiWrapper {
 
             // Slider valeuses
             time_per_step: Duration::new(1, 0),
-            zoom_ratio: 1.0
+            zoom_ratio: 1.0,
+            save_path: ImString::with_capacity(256),
+            ask_overwrite: false,
+            message: ImString::new(""),
+            pattern_info: vec![],
+            generation: 0,
+            timeline: RangeInclusive::new(0, 0),
+            goto_text: ImString::with_capacity(20),
+            goto: None,
         }
     }
 
@@ -83,12 +106,20 @@ impl ImGuiWrapper {
         self.imgui.io_mut().display_size = [draw_width, draw_height];
         self.imgui.io_mut().display_framebuffer_scale = [hidpi_factor, hidpi_factor];
 
+        let message = &self.message;
+        let save_path = &mut self.save_path;
+        let ask_overwrite = self.ask_overwrite;
+        let pattern_info = &self.pattern_info;
+        let goto_text = &mut self.goto_text;
         let ui = self.imgui.frame();
         {
             let mut slider = self.time_per_step.as_millis() as u64;
             let mut zoom_ratio = self.zoom_ratio;
             let slider_milli = Slider::new(im_str!("step time, in millisecond"), RangeInclusive::new(0, 2000));
             let slider_zoom = Slider::new(im_str!("zoom"), RangeInclusive::new(0.0, 2.0));
+            let slider_timeline = Slider::new(im_str!("generation"), self.timeline.clone());
+            let mut generation = self.generation;
+            let mut goto = None;
 
             let mut click_button = None;
             let (w, h) = graphics::size(ctx);
@@ -96,11 +127,22 @@ impl ImGuiWrapper {
             // Window
             Window::new(im_str!("Hello world"))
                 .menu_bar(false).title_bar(false).movable(false)
-                .resizable(false).size([w, 100.0], Condition::Always)
-                .position([0.0, h - 100.0], Condition::Always)
+                .resizable(false).size([w, WINDOW_HEIGHT], Condition::Always)
+                .position([0.0, h - WINDOW_HEIGHT], Condition::Always)
                 .build(&ui, || {
                     slider_milli.build(&ui, &mut slider);
                     slider_zoom.build(&ui, &mut zoom_ratio);
+                    ui.set_next_item_width(w / 2.0);
+                    if slider_timeline.build(&ui, &mut generation) {
+                        goto = Some(generation);
+                    }
+                    ui.same_line(0.0);
+                    ui.set_next_item_width(100.0);
+                    ui.input_text(im_str!("##goto"), goto_text).chars_decimal(true).build();
+                    ui.same_line(0.0);
+                    if ui.button(im_str!("Go"), [30.0, 20.0]) {
+                        goto = goto_text.to_str().trim().parse().ok();
+                    }
                     ui.separator();
                     if ui.button(im_str!("|<"),  [20.0, 20.0]) {
                         click_button = Some(UiButton::Prev);
@@ -124,9 +166,33 @@ impl ImGuiWrapper {
                     if ui.button(im_str!("Save init Map"),  [70.0, 20.0]) {
                         click_button = Some(UiButton::SaveInitMap);
                     }
+                    ui.same_line(234.0);
+                    ui.set_next_item_width(200.0);
+                    ui.input_text(im_str!("file"), save_path).resize_buffer(true).build();
+                    ui.same_line(0.0);
+                    if ui.button(im_str!("Analyse"), [70.0, 20.0]) {
+                        click_button = Some(UiButton::Analyse);
+                    }
+                    if ask_overwrite {
+                        ui.same_line(0.0);
+                        if ui.button(im_str!("Overwrite"), [70.0, 20.0]) {
+                            click_button = Some(UiButton::Overwrite);
+                        }
+                    }
+                    ui.same_line(0.0);
+                    ui.text(message);
+                });
+
+            // The name, author and comments of the pattern, in a window the user can fold
+            Window::new(im_str!("Pattern"))
+                .position([10.0, 10.0], Condition::FirstUseEver)
+                .always_auto_resize(true)
+                .build(&ui, || {
+                    pattern_info.iter().for_each(|line| ui.text(line));
                 });
             self.last_button = click_button;
-            self.time_per_step = Duration::from_millis(slider as u64);
+            self.goto = goto;
+            self.time_per_step = Duration::from_millis(slider);
             self.zoom_ratio = zoom_ratio;
         }
 
@@ -147,6 +213,48 @@ impl ImGuiWrapper {
         self.time_per_step
     }
 
+    /// Show a message to the user, like a save error
+    pub fn set_message(&mut self, message: &str) {
+        self.message = ImString::new(message);
+    }
+
+    /// Get the file typed by the user, None when the field is empty
+    pub fn get_save_path(&self) -> Option<String> {
+        let path = self.save_path.to_str().trim();
+
+        if path.is_empty() { None } else { Some(path.to_string()) }
+    }
+
+    pub fn set_save_path(&mut self, path: &str) {
+        self.save_path = ImString::new(path);
+    }
+
+    /// Show the overwrite button, to confirm the last save
+    pub fn set_ask_overwrite(&mut self, ask_overwrite: bool) {
+        self.ask_overwrite = ask_overwrite;
+    }
+
+    /// Set the generation shown on the timeline, and the generations it goes through
+    pub fn set_timeline(&mut self, generation: u64, timeline: RangeInclusive<u64>) {
+        self.generation = generation;
+        self.timeline = timeline;
+    }
+
+    /// Get the generation chosen on the timeline or typed, on the last frame
+    pub fn get_goto(&self) -> Option<u64> {
+        self.goto
+    }
+
+    /// Set the lines of the pattern window
+    pub fn set_pattern_info(&mut self, pattern_info: Vec<String>) {
+        self.pattern_info = pattern_info;
+    }
+
+    /// Is the mouse over a window, the clicks are then not for the board
+    pub fn is_mouse_captured(&self) -> bool {
+        self.imgui.io().want_capture_mouse
+    }
+
     pub fn get_last_button(&self) -> Option<UiButton> {
        self.last_button
     }
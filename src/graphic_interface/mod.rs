@@ -8,13 +8,19 @@ use ggez::{graphics, Context, GameResult};
 use ggez::event::{EventHandler};
 use ggez::event::MouseButton;
 
-use crate::board::Board;
+use crate::analysis::{analyse, Analysis, DEFAULT_MAX_GENERATIONS};
+use crate::board::cell::STATUS;
+use crate::board::rule::Rule;
+use crate::engine::LifeEngine;
+use crate::pattern::{Format, Pattern, PatternError};
 use constants::Constants;
 use camera::Camera;
-use im_gui_wrapper::ImGuiWrapper;
+use im_gui_wrapper::{ImGuiWrapper, WINDOW_HEIGHT};
 use std::time::Duration;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
 use crate::graphic_interface::im_gui_wrapper::UiButton;
-use crate::create_file_from_map;
+use crate::{autosave_path, create_file_from_map};
 
 /// `MyGame` describe the game graphic_interface logic
 /// It contain:
@@ -22,7 +28,7 @@ use crate::create_file_from_map;
 /// <p> - some static mesh  </p>
 /// <p> - data about the refresh rate and games constants  </p>
 pub struct MyGame {
-    board: Box<Board>,
+    engine: Box<dyn LifeEngine>,
     camera: Camera,
     constants: Constants,
 
@@ -35,7 +41,36 @@ pub struct MyGame {
     as_move: bool,
     last_refresh : time::Instant,
     game_step: i64,
-    play: bool
+    play: bool,
+    /// The save waiting for the user to confirm the overwrite: (initial map, file)
+    pending_save: Option<(bool, String)>,
+    /// The last analysis of the pattern, with the generation it was done at
+    analysis: Option<(u64, Analysis)>,
+    /// The analysis running on a worker thread, so the window keeps drawing, with the generation it started at
+    running_analysis: Option<(u64, Receiver<Analysis>)>,
+    /// The furthest generation reached, the end of the timeline
+    max_generation: u64,
+}
+
+/// Colour of the living cells
+const ALIVE_COLOR: (u8, u8, u8) = (255, 51, 255);
+
+/// Colour the dying cells of the Generations rules fade to, before they are dead
+const DYING_COLOR: (u8, u8, u8) = (255, 225, 160);
+
+/// Get the colour of a cell, the dying states are a ramp from the living colour to the dying one
+/// The states of a rule table have the colours of its `@COLORS`, when it gives them, and WireWorld has its own.
+fn status_color(status: STATUS, rule: &Rule) -> graphics::Color {
+    if let Some((r, g, b)) = rule.get_color(status.get_state()) {
+        return graphics::Color::from_rgb(r, g, b);
+    }
+    let ratio = match status {
+        STATUS::DYING(state) => (state - 1) as f32 / (rule.get_states() - 1) as f32,
+        _ => 0.0,
+    };
+    let mix = |alive: u8, dying: u8| (alive as f32 + (dying as f32 - alive as f32) * ratio) as u8;
+
+    graphics::Color::from_rgb(mix(ALIVE_COLOR.0, DYING_COLOR.0), mix(ALIVE_COLOR.1, DYING_COLOR.1), mix(ALIVE_COLOR.2, DYING_COLOR.2))
 }
 
 /// The impl is here to define our graphic_interface logic called by the `EventHandler`
@@ -46,45 +81,59 @@ impl MyGame {
 
 
         let line_h = graphics::Mesh::new_line(
-            ctx, &[ Point2{ x: 0.0, y: 0.0 as f32}, Point2{ x: w, y: 0.0 } ],
+            ctx, &[ Point2{ x: 0.0, y: 0.0}, Point2{ x: w, y: 0.0 } ],
             1.0,
             color,
         ).unwrap();
 
         let line_w = graphics::Mesh::new_line(
-            ctx, &[ Point2{x: 0.0 as f32, y: 0.0}, Point2{x: 0.0, y: h} ],
+            ctx, &[ Point2{x: 0.0, y: 0.0}, Point2{x: 0.0, y: h} ],
             1.0,
             color,
         ).unwrap();
         (line_h, line_w)
     }
 
+    /// Create the white square of a cell, or the hexagon of a hexagonal grid, it is coloured when drawn
     fn create_cell_mesh(ctx: &mut Context, camera: &Camera) -> graphics::Mesh {
-        let cell_mesh_rect = graphics::Rect::new(0.0, 0.0, camera.get_cell_size(), camera.get_cell_size());
-
-        match graphics::Mesh::new_rectangle(
-            ctx,
-            graphics::DrawMode::fill(),
-            cell_mesh_rect,
-            graphics::Color::from_rgb(255, 51, 255)
-        ) {
+        let size = camera.get_cell_size();
+        let cell_mesh_rect = graphics::Rect::new(0.0, 0.0, size, size);
+        let hexagon = [
+            Point2{x: size / 2.0, y: 0.0}, Point2{x: size, y: size / 4.0}, Point2{x: size, y: size * 3.0 / 4.0},
+            Point2{x: size / 2.0, y: size}, Point2{x: 0.0, y: size * 3.0 / 4.0}, Point2{x: 0.0, y: size / 4.0},
+        ];
+
+        let mesh = if camera.is_hexagonal() {
+            graphics::Mesh::new_polygon(ctx, graphics::DrawMode::fill(), &hexagon, graphics::WHITE)
+        } else {
+            graphics::Mesh::new_rectangle(ctx, graphics::DrawMode::fill(), cell_mesh_rect, graphics::WHITE)
+        };
+        match mesh {
             Ok(mesh) => mesh,
             Err(_e) => panic!("Could not create cell_mesh")
         }
     }
 
-    pub fn new(ctx: &mut Context, board: Box<Board>) -> Self {
+    pub fn new(ctx: &mut Context, engine: Box<dyn LifeEngine>, save_path: Option<String>) -> Self {
         let (w, h) = graphics::size(ctx);
-        let (board_h, board_w) = board.get_size();
         let (line_h, line_w) = MyGame::create_line_mesh(ctx, w, h);
 
-        let camera = Camera::new(
-            Point2{x: (board_w / 2 - 10) as f32, y: (board_h / 2 - 10) as f32},
+        // The camera start centered on the living cells, with 16 pixels per cell
+        let (center_x, center_y) = match engine.get_bounding_box() {
+            Some((s_x, s_y, b_x, b_y)) => ((s_x + b_x) as f32 / 2.0, (s_y + b_y) as f32 / 2.0),
+            None => (0.0, 0.0)
+        };
+        let mut camera = Camera::new(
+            Point2{x: center_x - w / 32.0, y: center_y - h / 32.0},
             Point2{x: w , y: h});
-        let img = ImGuiWrapper::new(ctx);
+        camera.set_hexagonal(engine.get_rule().get_neighbourhood().is_hexagonal());
+        let mut img = ImGuiWrapper::new(ctx);
+        if let Some(path) = save_path {
+            img.set_save_path(&path);
+        }
 
-        MyGame {
-            board,
+        let mut game = MyGame {
+            engine,
             constants: Constants::new(Duration::new(1, 0)),
             cell_mesh: Self::create_cell_mesh(ctx, &camera),
             img_wrapper: img,
@@ -95,45 +144,162 @@ impl MyGame {
             as_move: false,
             play: false,
             game_step: 0,
-            last_refresh: time::Instant::now()
-        }
+            last_refresh: time::Instant::now(),
+            pending_save: None,
+            analysis: None,
+            running_analysis: None,
+            max_generation: 0,
+        };
+        game.update_pattern_info();
+        game
     }
 
-    fn save_map(&self) {
-        create_file_from_map(&self.board.board_to_string(), "");
+    /// Show the metadata of the pattern, with the current rule and generation, and the timeline
+    fn update_pattern_info(&mut self) {
+        let generation = self.engine.get_generation();
+        self.max_generation = self.max_generation.max(generation);
+        self.img_wrapper.set_timeline(generation, self.engine.get_metadata().generation..=self.max_generation);
+
+        let metadata = self.engine.get_metadata();
+        let info = metadata.name.iter().map(|name| format!("Name: {}", name))
+            .chain(metadata.author.iter().map(|author| format!("Author: {}", author)))
+            .chain(Some(format!("Rule: {}", self.engine.get_rule())))
+            .chain(Some(format!("Generation: {}", self.engine.get_generation())))
+            .chain(self.engine.get_seed().map(|seed| format!("Seed: {}", seed)))
+            .chain(metadata.source_url.iter().map(|url| format!("Source: {}", url)))
+            .chain(metadata.comments.iter().cloned())
+            .chain(self.analysis.iter().flat_map(|(generation, analysis)| {
+                let mut lines = vec![format!("Analysis at generation {}:", generation)];
+                lines.extend(analysis.to_string().lines().map(String::from));
+                lines
+            }))
+            .collect();
+
+        self.img_wrapper.set_pattern_info(info);
     }
 
-    fn save_init_map(&self) {
-        create_file_from_map(&self.board.initial_board_to_string(), "");
+    /// Save the map, or the initial map, to the file chosen by the user or to a new autosave file
+    /// An existing file is only replaced after the user confirmed it
+    fn save(&mut self, initial: bool, path: Option<String>, overwrite: bool) {
+        let pattern = if initial {
+            Pattern::from_initial_engine(&*self.engine)
+        } else {
+            Pattern::from_engine(&*self.engine)
+        };
+        let path = path.unwrap_or_else(|| autosave_path(Format::Rle));
+        let res = create_file_from_map(&pattern, &path, overwrite);
+
+        self.pending_save = match res {
+            Err(PatternError::FileExists(_)) => Some((initial, path.clone())),
+            _ => None,
+        };
+        self.img_wrapper.set_ask_overwrite(self.pending_save.is_some());
+        self.show_save_result(&path, res);
     }
 
-    fn prev(&mut self) {
-        if self.constants.turns > 0 {
-            self.board.prev();
-            self.constants.turns -= 1;
+    fn show_save_result(&mut self, path: &str, res: Result<(), PatternError>) {
+        match res {
+            Ok(()) => self.img_wrapper.set_message(&format!("Map saved to {}", path)),
+            Err(e @ PatternError::FileExists(_)) => self.img_wrapper.set_message(&format!("{}, overwrite it?", e)),
+            Err(e) => self.img_wrapper.set_message(&format!("Save failed: {}", e)),
         }
     }
 
+    /// Go back one step, the engine does nothing when it has no history left
+    fn prev(&mut self) {
+        self.engine.prev();
+        self.update_pattern_info();
+    }
+
     fn next(&mut self) {
-        self.board.next();
-        self.constants.turns += 1;
+        self.engine.step();
+        self.update_pattern_info();
+    }
+
+    /// Go to a generation chosen on the timeline
+    fn goto(&mut self, generation: u64) {
+        if self.engine.goto(generation) {
+            self.update_pattern_info();
+        } else if generation < self.engine.get_metadata().generation {
+            self.img_wrapper.set_message(&format!("The generation {} is before the start of the pattern", generation));
+        } else {
+            self.img_wrapper.set_message(&format!("The generation {} is before the history, the cells set by hand would be lost", generation));
+        }
+    }
+
+    /// Analyse a copy of the board on a worker thread, the result is shown by `poll_analysis` once it is found
+    fn start_analysis(&mut self) {
+        if self.running_analysis.is_some() {
+            self.img_wrapper.set_message("An analysis is already running");
+            return;
+        }
+        let board = self.engine.to_board();
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            // The window may be closed before the analysis ends, nobody waits for it then
+            let _ = sender.send(analyse(&board, DEFAULT_MAX_GENERATIONS));
+        });
+        self.running_analysis = Some((self.engine.get_generation(), receiver));
+        self.img_wrapper.set_message("Analysing the pattern...");
+    }
+
+    /// Show the analysis running on the worker thread, when it is done
+    fn poll_analysis(&mut self) {
+        let res = match &self.running_analysis {
+            Some((_, receiver)) => receiver.try_recv(),
+            None => return,
+        };
+        match res {
+            Err(TryRecvError::Empty) => {}
+            Ok(analysis) => {
+                let (generation, _) = self.running_analysis.take().unwrap();
+                self.analysis = Some((generation, analysis));
+                self.img_wrapper.set_message("Analysis done");
+                self.update_pattern_info();
+            }
+            Err(TryRecvError::Disconnected) => {
+                self.running_analysis = None;
+                self.img_wrapper.set_message("The analysis failed");
+            }
+        }
     }
 
     /// Draw each line limitation of the board
+    /// The columns of a hexagonal grid are not straight, only the rows are drawn.
     fn draw_line(&self, ctx: &mut Context) -> GameResult<()> {
         self.camera.line_to_show().iter()
-            .fold(Ok(()), | _acc, pixel|{
-                graphics::draw(ctx, &self.line_w, ( pixel.screen_pos, ))?;
+            .try_for_each(|pixel| {
+                if !self.camera.is_hexagonal() {
+                    graphics::draw(ctx, &self.line_w, ( pixel.screen_pos, ))?;
+                }
                 graphics::draw(ctx, &self.line_h, ( pixel.screen_pos, ))
             })
     }
 
-    /// Draw the living cells on the board
+    /// Draw the edges of a bounded board
+    fn draw_boundary(&self, ctx: &mut Context) -> GameResult<()> {
+        if let Some(size) = self.engine.get_rule().get_topology().get_size() {
+            let (x, y, w, h) = self.camera.boundary_to_show(size);
+            let boundary = graphics::Mesh::new_rectangle(
+                ctx,
+                graphics::DrawMode::stroke(3.0),
+                graphics::Rect::new(x, y, w, h),
+                graphics::Color::from_rgb(51, 51, 255)
+            )?;
+            graphics::draw(ctx, &boundary, graphics::DrawParam::default())?;
+        }
+        Ok(())
+    }
+
+    /// Draw the living and dying cells on the board, with the colour of their state
     fn draw_board(&self, ctx: &mut Context) -> GameResult<()> {
-        self.camera.active_cells_to_show(self.board.get_leaving_cells()).iter()
-            .fold(Ok(()), | _acc, pixel|
+        let cells = self.engine.get_cells_in(self.camera.get_visible_rect());
+        let rule = self.engine.get_rule();
+
+        self.camera.active_cells_to_show(&cells).iter().zip(cells.iter())
+            .try_for_each(|(pixel, cell)|
                 graphics::draw(ctx, &self.cell_mesh,
-                    graphics::DrawParam::default().dest(pixel.screen_pos))
+                    graphics::DrawParam::default().dest(pixel.screen_pos).color(status_color(cell.status, rule)))
             )
     }
 
@@ -143,10 +309,20 @@ impl MyGame {
             Some(UiButton::Prev) => { self.game_step -= 1;}
             Some(UiButton::Stop) => { self.play = false; }
             Some(UiButton::Play) => { self.play = true; }
-            Some(UiButton::SaveMap) => { self.save_map(); }
-            Some(UiButton::SaveInitMap) => { self.save_init_map(); }
+            Some(UiButton::SaveMap) => { self.save(false, self.img_wrapper.get_save_path(), false); }
+            Some(UiButton::SaveInitMap) => { self.save(true, self.img_wrapper.get_save_path(), false); }
+            Some(UiButton::Analyse) => { self.start_analysis(); }
+            Some(UiButton::Overwrite) => {
+                if let Some((initial, path)) = self.pending_save.take() {
+                    self.save(initial, Some(path), true);
+                }
+            }
             _ => {}
         }
+        if let Some(generation) = self.img_wrapper.get_goto() {
+            self.play = false;
+            self.goto(generation);
+        }
         self.constants.refresh_rate = self.img_wrapper.get_time_per_step();
     }
 }
@@ -157,6 +333,7 @@ impl EventHandler for MyGame {
     /// There for we call the board function that return a new one with the rules applied on all cells.
     fn update(&mut self, _ctx: &mut Context) -> GameResult<()> {
         self.update_button();
+        self.poll_analysis();
 
         let duration = time::Instant::now() - self.last_refresh;
         if duration > self.constants.refresh_rate && self.play {
@@ -185,6 +362,7 @@ impl EventHandler for MyGame {
         }
         self.draw_board(ctx)?;
         self.draw_line(ctx)?;
+        self.draw_boundary(ctx)?;
         self.img_wrapper.render(ctx, 2.0, self.play);
 
         graphics::present(ctx)
@@ -202,7 +380,7 @@ impl EventHandler for MyGame {
         self.img_wrapper.update_mouse_down(button);
 
         let (_w, h) = graphics::size(ctx);
-        if y <= h - 100.0 {
+        if y <= h - WINDOW_HEIGHT && !self.img_wrapper.is_mouse_captured() {
                 self.is_clicking = true;
         }
     }
@@ -218,10 +396,15 @@ impl EventHandler for MyGame {
         self.img_wrapper.update_mouse_up(button);
         let (_w, h) = graphics::size(ctx);
 
-        if y <= h - 100.0 && !self.as_move {
+        if y <= h - WINDOW_HEIGHT && !self.as_move && !self.img_wrapper.is_mouse_captured() {
             let  (w, h) = self.camera.board_pos_from_screen_pos((x, y));
+            let (x, y) = (w.floor() as i32, h.floor() as i32);
 
-            self.board.inverse_cell(w as i32, h as i32);
+            // A click paints the next state of the rule, a right click empties the cell
+            match button {
+                MouseButton::Right => { self.engine.set_cell(x, y, STATUS::DEAD); }
+                _ => { self.engine.cycle_cell(x, y); }
+            }
         }
         self.as_move = false;
         self.is_clicking = false;
@@ -232,7 +415,7 @@ impl EventHandler for MyGame {
         self.as_move = true;
         let (_w, h) = graphics::size(ctx);
 
-        if self.is_clicking && y <= h - 100.0 {
+        if self.is_clicking && y <= h - WINDOW_HEIGHT {
             self.camera.move_pos(Point2{x: dx, y: dy});
         }
     }
@@ -0,0 +1,692 @@
+mod node;
+
+use std::collections::{HashMap, VecDeque};
+use std::convert::TryFrom;
+
+use crate::board::ADJ_POSITIONS;
+use crate::board::cell::{Cell, STATUS};
+use crate::board::history::HistoryDepth;
+use crate::board::rule::Rule;
+use crate::engine::LifeEngine;
+use crate::pattern::macrocell::{Macrocell, MacroNode, LEAF_LEVEL};
+use crate::pattern::PatternMetadata;
+use node::{Node, NodeId, DEAD, ALIVE};
+
+/// Above this number of nodes the unreachable ones are dropped
+const MAX_NODES: usize = 4_000_000;
+
+/// Highest level of the root, the positions of its corners still fit in an i64
+const MAX_LEVEL: u32 = 61;
+
+/// Highest jump of 2^j generations, a jump needs a root of level j + 3
+pub const MAX_STEP_EXPONENT: u32 = MAX_LEVEL - 3;
+
+/// Most generations `step_n` can advance at once
+pub const MAX_GENERATIONS: u64 = (1 << (MAX_STEP_EXPONENT + 1)) - 1;
+
+/// Get a position as an i32, the positions out of the range are moved to its ends
+#[inline]
+fn clamp_i32(pos: i64) -> i32 {
+    i32::try_from(pos).unwrap_or(if pos < 0 { i32::MIN } else { i32::MAX })
+}
+
+/// A HashLife universe
+/// The live cells are stored in a quadtree where every node is unique,
+/// so a repeated part of the pattern is computed only once.
+/// The result of a node after 2^j generations is memoized, which let it jump
+/// millions of generations on regular patterns.
+pub struct HashLife {
+    nodes: Vec<Node>,
+    ids: HashMap<[NodeId; 4], NodeId>,
+    results: HashMap<(NodeId, u32), NodeId>,
+    empty: Vec<NodeId>,
+
+    root: NodeId,
+    /// Position of the top left corner of the root
+    origin: (i64, i64),
+    generation: u64,
+    /// `next` advances 2^step_exponent generations
+    step_exponent: u32,
+
+    /// The previous roots, the nodes are shared so a step costs only its new nodes
+    history: VecDeque<(NodeId, (i64, i64), u64)>,
+    history_depth: HistoryDepth,
+    initial_state: (NodeId, (i64, i64)),
+    /// A cell was set by hand, the initial cells don't lead to the current ones anymore
+    edited: bool,
+    rule: Rule,
+    metadata: PatternMetadata,
+}
+
+/// Define the quadtree logic
+impl HashLife {
+    pub fn new(cells: &[Cell], rule: Rule) -> Self {
+        let mut life = HashLife {
+            nodes: vec![Node::leaf(false), Node::leaf(true)],
+            ids: HashMap::new(),
+            results: HashMap::new(),
+            empty: vec![DEAD],
+            root: DEAD,
+            origin: (0, 0),
+            generation: 0,
+            step_exponent: 0,
+            history: VecDeque::new(),
+            history_depth: HistoryDepth::default(),
+            initial_state: (DEAD, (0, 0)),
+            edited: false,
+            rule,
+            metadata: PatternMetadata::default(),
+        };
+
+        life.root = life.empty(3);
+        cells.iter().filter(|cell| cell.is_alive())
+            .for_each(|cell| life.put_cell(cell.x, cell.y, true));
+        life.initial_state = (life.root, life.origin);
+        life
+    }
+
+    /// Construct the universe straight from a macrocell quadtree, without listing its cells
+    pub fn from_macrocell(macrocell: &Macrocell, rule: Rule) -> Self {
+        let mut life = HashLife::new(&[], rule);
+        let mut ids = Vec::with_capacity(macrocell.nodes.len());
+
+        for node in macrocell.nodes.iter() {
+            let id = match *node {
+                MacroNode::Leaf(bits) => life.join_bits(bits, (0, 0), LEAF_LEVEL),
+                MacroNode::Node { level, children } => {
+                    let mut quadrants = [DEAD; 4];
+                    for (quadrant, &child) in quadrants.iter_mut().zip(children.iter()) {
+                        *quadrant = match child {
+                            0 => life.empty(level - 1),
+                            child => ids[child - 1],
+                        };
+                    }
+                    life.join(quadrants)
+                }
+            };
+            ids.push(id);
+        }
+        if let Some(&root) = ids.last() {
+            life.root = root;
+            life.origin = macrocell.get_origin();
+        }
+        life.initial_state = (life.root, life.origin);
+        life.with_metadata(macrocell.metadata.clone())
+    }
+
+    /// Attach the metadata of the loaded pattern, the universe starts at its generation
+    pub fn with_metadata(self, metadata: PatternMetadata) -> Self {
+        HashLife { generation: metadata.generation, metadata, ..self }
+    }
+
+    /// Get the node of the cells at (x, y) in a 8x8 leaf, the bit `y * 8 + x` is set for a living cell
+    fn join_bits(&mut self, bits: u64, (x, y): (u32, u32), level: u32) -> NodeId {
+        if level == 0 {
+            return if bits & (1 << (y * 8 + x)) != 0 { ALIVE } else { DEAD };
+        }
+        let half = 1 << (level - 1);
+        let children = [
+            self.join_bits(bits, (x, y), level - 1),
+            self.join_bits(bits, (x + half, y), level - 1),
+            self.join_bits(bits, (x, y + half), level - 1),
+            self.join_bits(bits, (x + half, y + half), level - 1),
+        ];
+        self.join(children)
+    }
+
+    #[inline]
+    fn node(&self, id: NodeId) -> &Node {
+        &self.nodes[id as usize]
+    }
+
+    #[inline]
+    fn level(&self, id: NodeId) -> u32 {
+        self.node(id).level
+    }
+
+    #[inline]
+    fn population(&self, id: NodeId) -> u64 {
+        self.node(id).population
+    }
+
+    /// Get the unique node made of those four children: nw, ne, sw, se
+    fn join(&mut self, children: [NodeId; 4]) -> NodeId {
+        if let Some(&id) = self.ids.get(&children) {
+            return id;
+        }
+        let level = self.level(children[0]) + 1;
+        let population = children.iter().map(|&c| self.population(c)).sum();
+        let id = self.nodes.len() as NodeId;
+
+        self.nodes.push(Node { children, level, population });
+        self.ids.insert(children, id);
+        id
+    }
+
+    /// Get the empty node of a level
+    fn empty(&mut self, level: u32) -> NodeId {
+        while self.empty.len() <= level as usize {
+            let e = *self.empty.last().unwrap();
+            let id = self.join([e, e, e, e]);
+            self.empty.push(id);
+        }
+        self.empty[level as usize]
+    }
+
+    /// Get the middle square of a node, one level lower
+    fn centre(&mut self, id: NodeId) -> NodeId {
+        let [nw, ne, sw, se] = self.node(id).children;
+
+        self.join([self.node(nw).children[3], self.node(ne).children[2],
+                   self.node(sw).children[1], self.node(se).children[0]])
+    }
+
+    /// Get the node made of the east half of `w` and the west half of `e`
+    fn horizontal(&mut self, w: NodeId, e: NodeId) -> NodeId {
+        let (w, e) = (self.node(w).children, self.node(e).children);
+        self.join([w[1], e[0], w[3], e[2]])
+    }
+
+    /// Get the node made of the south half of `n` and the north half of `s`
+    fn vertical(&mut self, n: NodeId, s: NodeId) -> NodeId {
+        let (n, s) = (self.node(n).children, self.node(s).children);
+        self.join([n[2], n[3], s[0], s[1]])
+    }
+
+    #[inline]
+    fn size(&self) -> i64 {
+        1 << self.level(self.root)
+    }
+
+    /// Double the size of the universe, keeping the pattern in the middle
+    fn expand(&mut self) {
+        let level = self.level(self.root);
+        let e = self.empty(level - 1);
+        let [nw, ne, sw, se] = self.node(self.root).children;
+
+        let nw = self.join([e, e, e, nw]);
+        let ne = self.join([e, e, ne, e]);
+        let sw = self.join([e, sw, e, e]);
+        let se = self.join([se, e, e, e]);
+        self.origin = (self.origin.0 - (1 << (level - 1)), self.origin.1 - (1 << (level - 1)));
+        self.root = self.join([nw, ne, sw, se]);
+    }
+
+    /// Halve the universe as long as no living cell is lost
+    fn shrink(&mut self) {
+        while self.level(self.root) > 3 {
+            let centre = self.centre(self.root);
+            if self.population(centre) != self.population(self.root) {
+                break;
+            }
+            let quarter = 1 << (self.level(self.root) - 2);
+            self.origin = (self.origin.0 + quarter, self.origin.1 + quarter);
+            self.root = centre;
+        }
+    }
+
+    #[inline]
+    fn contains(&self, x: i64, y: i64) -> bool {
+        x >= self.origin.0 && y >= self.origin.1
+            && x < self.origin.0 + self.size() && y < self.origin.1 + self.size()
+    }
+
+    /// Is the cell at this position of the node alive
+    fn is_alive_in(&self, id: NodeId, x: i64, y: i64) -> bool {
+        let node = self.node(id);
+        if node.population == 0 {
+            false
+        } else if node.level == 0 {
+            id == ALIVE
+        } else {
+            let half = 1 << (node.level - 1);
+            let quadrant = (x >= half) as usize + 2 * (y >= half) as usize;
+            self.is_alive_in(node.children[quadrant], x % half, y % half)
+        }
+    }
+
+    /// Get the node with the cell at this position set
+    fn set_in(&mut self, id: NodeId, x: i64, y: i64, alive: bool) -> NodeId {
+        let node = *self.node(id);
+        if node.level == 0 {
+            if alive { ALIVE } else { DEAD }
+        } else {
+            let half = 1 << (node.level - 1);
+            let quadrant = (x >= half) as usize + 2 * (y >= half) as usize;
+            let mut children = node.children;
+
+            children[quadrant] = self.set_in(children[quadrant], x % half, y % half, alive);
+            self.join(children)
+        }
+    }
+
+    /// Set the cell at this position, the universe is expanded until it contains it
+    fn put_cell(&mut self, x: i32, y: i32, alive: bool) {
+        let (x, y) = (x as i64, y as i64);
+
+        while !self.contains(x, y) {
+            self.expand();
+        }
+        self.root = self.set_in(self.root, x - self.origin.0, y - self.origin.1, alive);
+    }
+
+    /// Compute the 2x2 centre of a 4x4 node after one generation
+    fn successor_leaf(&mut self, id: NodeId) -> NodeId {
+        let alive = |x: i64, y: i64| self.is_alive_in(id, x, y);
+        let next = |x: i64, y: i64| {
+            let neighbourhood = ADJ_POSITIONS.iter().enumerate()
+                .filter(|(_, &(dx, dy))| alive(x + dx as i64, y + dy as i64))
+                .fold(0, |neighbourhood, (i, _)| neighbourhood | (1 << i));
+            if self.rule.apply(alive(x, y), neighbourhood) { ALIVE } else { DEAD }
+        };
+        let children = [next(1, 1), next(2, 1), next(1, 2), next(2, 2)];
+
+        self.join(children)
+    }
+
+    /// Get the centre of a node of level k advanced by 2^j generations, with j <= k - 2
+    fn successor(&mut self, id: NodeId, j: u32) -> NodeId {
+        let level = self.level(id);
+        if self.population(id) == 0 {
+            return self.empty(level - 1);
+        }
+        if let Some(&res) = self.results.get(&(id, j)) {
+            return res;
+        }
+
+        let res = if level == 2 {
+            self.successor_leaf(id)
+        } else {
+            let [nw, ne, sw, se] = self.node(id).children;
+            let n = self.horizontal(nw, ne);
+            let w = self.vertical(nw, sw);
+            let c = self.centre(id);
+            let e = self.vertical(ne, se);
+            let s = self.horizontal(sw, se);
+            let sub = [nw, n, ne, w, c, e, sw, s, se];
+
+            // At full speed both halves of the jump are done, else only the second one
+            let full_speed = j == level - 2;
+            let mut r = [DEAD; 9];
+            for (i, &node) in sub.iter().enumerate() {
+                r[i] = if full_speed { self.successor(node, level - 3) } else { self.centre(node) };
+            }
+
+            let next_j = if full_speed { level - 3 } else { j };
+            let quarters = [[r[0], r[1], r[3], r[4]], [r[1], r[2], r[4], r[5]],
+                            [r[3], r[4], r[6], r[7]], [r[4], r[5], r[7], r[8]]];
+            let mut children = [DEAD; 4];
+            for (i, &quarter) in quarters.iter().enumerate() {
+                let joined = self.join(quarter);
+                children[i] = self.successor(joined, next_j);
+            }
+            self.join(children)
+        };
+
+        self.results.insert((id, j), res);
+        res
+    }
+
+    /// Advance the universe by 2^j generations
+    /// False when nothing is done, the root would be higher than `MAX_LEVEL` or the generation would overflow.
+    pub fn step_pow2(&mut self, j: u32) -> bool {
+        let generation = match self.generation.checked_add(1 << j.min(63)) {
+            Some(generation) if j <= MAX_STEP_EXPONENT => generation,
+            _ => return false,
+        };
+
+        self.shrink();
+        loop {
+            let level = self.level(self.root);
+            let centre = self.centre(self.root);
+            let inner = self.centre(centre);
+            if level >= j + 3 && self.population(inner) == self.population(self.root) {
+                break;
+            }
+            if level >= MAX_LEVEL {
+                return false;
+            }
+            self.expand();
+        }
+
+        self.history.push_front((self.root, self.origin, self.generation));
+        while !self.history_depth.allows(self.history.len()) {
+            self.history.pop_back();
+        }
+
+        let quarter = 1 << (self.level(self.root) - 2);
+        self.root = self.successor(self.root, j);
+        self.origin = (self.origin.0 + quarter, self.origin.1 + quarter);
+        self.generation = generation;
+
+        if self.nodes.len() > MAX_NODES {
+            self.collect_garbage();
+        }
+        true
+    }
+
+    /// Copy the nodes still in use in a new universe, and forget all the results
+    fn collect_garbage(&mut self) {
+        let mut life = HashLife {
+            nodes: vec![Node::leaf(false), Node::leaf(true)],
+            ids: HashMap::new(),
+            results: HashMap::new(),
+            empty: vec![DEAD],
+            root: DEAD,
+            origin: self.origin,
+            generation: self.generation,
+            step_exponent: self.step_exponent,
+            history: VecDeque::new(),
+            history_depth: self.history_depth,
+            initial_state: (DEAD, self.initial_state.1),
+            edited: self.edited,
+            rule: self.rule.clone(),
+            metadata: std::mem::take(&mut self.metadata),
+        };
+        let mut copied = HashMap::new();
+
+        life.root = self.copy_into(self.root, &mut life, &mut copied);
+        life.initial_state.0 = self.copy_into(self.initial_state.0, &mut life, &mut copied);
+        for &(root, origin, generation) in self.history.iter() {
+            let root = self.copy_into(root, &mut life, &mut copied);
+            life.history.push_back((root, origin, generation));
+        }
+        *self = life;
+    }
+
+    fn copy_into(&self, id: NodeId, life: &mut HashLife, copied: &mut HashMap<NodeId, NodeId>) -> NodeId {
+        if self.level(id) == 0 {
+            return id;
+        }
+        if let Some(&new_id) = copied.get(&id) {
+            return new_id;
+        }
+        let mut children = self.node(id).children;
+        for child in children.iter_mut() {
+            *child = self.copy_into(*child, life, copied);
+        }
+        let new_id = life.join(children);
+        copied.insert(id, new_id);
+        new_id
+    }
+
+    #[inline]
+    pub fn get_step_exponent(&self) -> u32 {
+        self.step_exponent
+    }
+
+    #[inline]
+    pub fn set_step_exponent(&mut self, step_exponent: u32) {
+        self.step_exponent = step_exponent;
+    }
+
+    /// Get the living cells of a node inside the rectangle (min_x, min_y, max_x, max_y)
+    /// The cells too far for an i32 position are skipped.
+    fn cells_in(&self, id: NodeId, (x, y): (i64, i64), rect: (i64, i64, i64, i64), cells: &mut Vec<Cell>) {
+        let node = self.node(id);
+        let size = 1 << node.level;
+
+        if node.population == 0 || x > rect.2 || y > rect.3 || x + size <= rect.0 || y + size <= rect.1 {
+            return;
+        }
+        if node.level == 0 {
+            if let (Ok(x), Ok(y)) = (i32::try_from(x), i32::try_from(y)) {
+                cells.push(Cell::new(x, y, STATUS::ALIVE));
+            }
+        } else {
+            let half = size / 2;
+            let children = node.children;
+            self.cells_in(children[0], (x, y), rect, cells);
+            self.cells_in(children[1], (x + half, y), rect, cells);
+            self.cells_in(children[2], (x, y + half), rect, cells);
+            self.cells_in(children[3], (x + half, y + half), rect, cells);
+        }
+    }
+
+    /// Get the position of the first living column, or row, of a node
+    /// `quadrants` are the children to look in first, then the others.
+    fn edge(&self, id: NodeId, quadrants: ([usize; 2], [usize; 2]), min: bool) -> Option<i64> {
+        let node = self.node(id);
+        if node.population == 0 {
+            return None;
+        }
+        if node.level == 0 {
+            return Some(0);
+        }
+        let half = 1 << (node.level - 1);
+        let (first, second) = quadrants;
+        let (first, offset_first, second, offset_second) = if min {
+            (first, 0, second, half)
+        } else {
+            (second, half, first, 0)
+        };
+        let in_quadrants = |children: [usize; 2], offset: i64| children.iter()
+            .filter_map(|&c| self.edge(node.children[c], quadrants, min))
+            .map(|pos| pos + offset)
+            .reduce(|a, b| if min { a.min(b) } else { a.max(b) });
+
+        in_quadrants(first, offset_first).or_else(|| in_quadrants(second, offset_second))
+    }
+
+}
+
+impl LifeEngine for HashLife {
+    /// Advance the universe by 2^step_exponent generations
+    fn step(&mut self) {
+        self.step_pow2(self.step_exponent);
+    }
+
+    /// Advance the universe by n generations, in jumps of powers of two
+    /// It stops at the first jump the universe can't hold, see `step_pow2`.
+    fn step_n(&mut self, n: u64) {
+        for j in (0..64).filter(|&j| n & (1 << j) != 0) {
+            if !self.step_pow2(j) {
+                break;
+            }
+        }
+    }
+
+    fn prev(&mut self) {
+        if let Some((root, origin, generation)) = self.history.pop_front() {
+            self.root = root;
+            self.origin = origin;
+            self.generation = generation;
+        }
+    }
+
+    /// Go to a generation, backward from the history or the initial cells, forward in jumps of powers of two
+    /// The initial cells are not used once a cell was set by hand, the edits would be lost.
+    fn goto(&mut self, generation: u64) -> bool {
+        if generation < self.metadata.generation {
+            return false;
+        }
+        let oldest = self.history.back().map_or(self.generation, |&(_, _, g)| g);
+        if generation < oldest && self.edited {
+            return false;
+        }
+        if generation < self.generation {
+            match self.history.iter().position(|&(_, _, g)| g <= generation) {
+                Some(index) => {
+                    let (root, origin, g) = self.history[index];
+                    self.history.drain(..=index);
+                    self.root = root;
+                    self.origin = origin;
+                    self.generation = g;
+                }
+                None => {
+                    let (root, origin) = self.initial_state;
+                    self.history.clear();
+                    self.root = root;
+                    self.origin = origin;
+                    self.generation = self.metadata.generation;
+                }
+            }
+        }
+        self.step_n(generation - self.generation);
+        true
+    }
+
+    fn set_history_depth(&mut self, depth: HistoryDepth) {
+        self.history_depth = depth;
+        while !self.history_depth.allows(self.history.len()) {
+            self.history.pop_back();
+        }
+    }
+
+    fn get_cell(&self, x: i32, y: i32) -> Cell {
+        let (x64, y64) = (x as i64, y as i64);
+
+        if self.contains(x64, y64) && self.is_alive_in(self.root, x64 - self.origin.0, y64 - self.origin.1) {
+            Cell::new(x, y, STATUS::ALIVE)
+        } else {
+            Cell::new(x, y, STATUS::DEAD)
+        }
+    }
+
+    fn set_cell(&mut self, x: i32, y: i32, status: STATUS) -> Cell {
+        if self.get_cell(x, y).is_alive() != status.is_alive() {
+            self.edited = true;
+            self.put_cell(x, y, status.is_alive());
+        }
+        Cell::new(x, y, status)
+    }
+
+    /// Get the living cells inside the rectangle (min_x, min_y, max_x, max_y)
+    fn get_cells_in(&self, (min_x, min_y, max_x, max_y): (i32, i32, i32, i32)) -> Vec<Cell> {
+        let mut cells = vec![];
+        let rect = (min_x as i64, min_y as i64, max_x as i64, max_y as i64);
+
+        self.cells_in(self.root, self.origin, rect, &mut cells);
+        cells
+    }
+
+    fn get_initial_cells(&self) -> Vec<Cell> {
+        let (root, origin) = self.initial_state;
+        let mut cells = vec![];
+
+        self.cells_in(root, origin, (i64::MIN, i64::MIN, i64::MAX, i64::MAX), &mut cells);
+        cells
+    }
+
+    fn get_population(&self) -> u64 {
+        self.population(self.root)
+    }
+
+    /// The edges too far for an i32 position are moved to the ends of its range
+    fn get_bounding_box(&self) -> Option<(i32, i32, i32, i32)> {
+        let columns = ([0, 2], [1, 3]);
+        let rows = ([0, 1], [2, 3]);
+
+        Some((
+            clamp_i32(self.origin.0 + self.edge(self.root, columns, true)?),
+            clamp_i32(self.origin.1 + self.edge(self.root, rows, true)?),
+            clamp_i32(self.origin.0 + self.edge(self.root, columns, false)?),
+            clamp_i32(self.origin.1 + self.edge(self.root, rows, false)?),
+        ))
+    }
+
+    fn get_generation(&self) -> u64 {
+        self.generation
+    }
+
+    fn get_rule(&self) -> &Rule {
+        &self.rule
+    }
+
+    fn get_metadata(&self) -> &PatternMetadata {
+        &self.metadata
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+    use crate::board::cell::fixtures::{alive, positions};
+
+    const GLIDER: [(i32, i32); 5] = [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)];
+    const R_PENTOMINO: [(i32, i32); 5] = [(1, 0), (2, 0), (0, 1), (1, 1), (1, 2)];
+
+    /// Check that jumps of 2^k generations give the cells of as many board generations
+    fn check_against_board(pattern: &[(i32, i32)], max_exponent: u32) {
+        let mut board = Board::new(&alive(pattern), Rule::default());
+        let mut generation = 0;
+        for k in 0..=max_exponent {
+            let mut life = HashLife::new(&alive(pattern), Rule::default());
+            life.step_n(1 << k);
+            while generation < 1 << k {
+                board.next();
+                generation += 1;
+            }
+            assert_eq!(life.get_generation(), 1 << k);
+            assert_eq!(positions(&life), positions(&board), "after 2^{} generations", k);
+        }
+    }
+
+    #[test]
+    fn glider_matches_board() {
+        check_against_board(&GLIDER, 6);
+    }
+
+    #[test]
+    fn r_pentomino_matches_board() {
+        check_against_board(&R_PENTOMINO, 9);
+    }
+
+    #[test]
+    fn step_n_sums_powers_of_two() {
+        let mut life = HashLife::new(&alive(&R_PENTOMINO), Rule::default());
+        let mut board = Board::new(&alive(&R_PENTOMINO), Rule::default());
+        life.step_n(100);
+        (0..100).for_each(|_| board.next());
+
+        assert_eq!(life.get_generation(), 100);
+        assert_eq!(positions(&life), positions(&board));
+    }
+
+    #[test]
+    fn prev_restores_previous_step() {
+        let mut life = HashLife::new(&alive(&R_PENTOMINO), Rule::default());
+        life.step_n(10);
+        let before = positions(&life);
+        life.set_step_exponent(3);
+        life.step();
+        assert_eq!(life.get_generation(), 18);
+
+        life.prev();
+        assert_eq!(life.get_generation(), 10);
+        assert_eq!(positions(&life), before);
+    }
+
+    #[test]
+    fn goto_forward_and_backward() {
+        let mut life = HashLife::new(&alive(&GLIDER), Rule::default());
+        let mut board = Board::new(&alive(&GLIDER), Rule::default());
+        (0..20).for_each(|_| board.next());
+
+        assert!(life.goto(37));
+        assert_eq!(life.get_generation(), 37);
+        assert!(life.goto(20));
+        assert_eq!(life.get_generation(), 20);
+        assert_eq!(positions(&life), positions(&board));
+
+        let mut glider = GLIDER.to_vec();
+        glider.sort_unstable();
+        assert!(life.goto(0));
+        assert_eq!(life.get_generation(), 0);
+        assert_eq!(positions(&life), glider);
+    }
+
+    #[test]
+    fn far_generations_stop_at_the_highest_level() {
+        let mut life = HashLife::new(&alive(&GLIDER), Rule::default());
+        life.step_n(u64::MAX);
+
+        assert_eq!(life.get_generation(), MAX_GENERATIONS);
+        assert_eq!(life.get_population(), 5);
+        assert_eq!(life.get_bounding_box(), Some((i32::MAX, i32::MAX, i32::MAX, i32::MAX)));
+        assert!(life.get_cells_in((i32::MIN, i32::MIN, i32::MAX, i32::MAX)).is_empty());
+        assert!(!life.step_pow2(MAX_STEP_EXPONENT + 1));
+        assert_eq!(life.get_generation(), MAX_GENERATIONS);
+    }
+}
@@ -0,0 +1,21 @@
+/// Index of a node in the HashLife arena
+pub type NodeId = u32;
+
+/// The two cells, they are the only nodes of level 0
+pub const DEAD: NodeId = 0;
+pub const ALIVE: NodeId = 1;
+
+/// A square of 2^level cells per side
+/// Its children are the four quadrants: nw, ne, sw, se
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub struct Node {
+    pub children: [NodeId; 4],
+    pub level: u32,
+    pub population: u64,
+}
+
+impl Node {
+    pub fn leaf(alive: bool) -> Self {
+        Node { children: [DEAD; 4], level: 0, population: alive as u64 }
+    }
+}
@@ -1,32 +1,153 @@
+pub mod analysis;
 pub mod board;
+pub mod hashlife;
+pub mod engine;
+pub mod pattern;
+pub mod tiles;
 pub mod graphic_interface;
 
 use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
 use ggez::{ ContextBuilder, event, conf };
 
 use board::{Board};
+use board::history::HistoryDepth;
+use board::rule::Rule;
+use board::stochastic;
+use board::topology::Topology;
+use engine::{EngineKind, LifeEngine};
+use hashlife::HashLife;
+use pattern::{macrocell, Format, Pattern, PatternError};
+use tiles::TileBoard;
 use graphic_interface::MyGame;
 
-pub const MAP_SIZE: usize = 1000;
+/// How to load a map and run it
+/// <p> - rule: overrides the rule of the file </p>
+/// <p> - topology: overrides the topology of the rule </p>
+/// <p> - engine: the simulation backend, with HashLife each step is 2^step_exponent generations </p>
+/// <p> - history: how many steps can be undone </p>
+/// <p> - seed: the seed of a stochastic rule, overrides the one of the file </p>
+/// <p> - save_path: file the save buttons write to, a timestamped autosave file without it </p>
+#[derive(Clone, Debug, Default)]
+pub struct RunOptions {
+    pub rule: Option<Rule>,
+    pub topology: Option<Topology>,
+    pub engine: EngineKind,
+    pub step_exponent: u32,
+    pub history: HistoryDepth,
+    pub seed: Option<u64>,
+    pub save_path: Option<String>,
+}
+
+/// Get the (year, month, day) of a number of days since 1970-01-01
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+
+    (yoe + era * 400 + (month <= 2) as i64, month, day)
+}
+
+/// Get a new save file name with the current UTC time, like `./map/autosave_2021-03-04_15-30-00.rle`
+pub fn autosave_path(format: Format) -> String {
+    let secs = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0) as i64;
+    let (year, month, day) = civil_from_days(secs.div_euclid(86_400));
+    let time = secs.rem_euclid(86_400);
+
+    format!("./map/autosave_{}-{:02}-{:02}_{:02}-{:02}-{:02}.{}",
+            year, month, day, time / 3600, time / 60 % 60, time % 60, format.extension())
+}
+
+/// Write a file, an existing one is only replaced with `overwrite`
+fn write_file(file_path: &str, value: String, overwrite: bool) -> Result<(), PatternError> {
+    if !overwrite && Path::new(file_path).exists() {
+        return Err(PatternError::FileExists(file_path.to_string()));
+    }
+    fs::write(file_path, value)?;
+    Ok(())
+}
+
+/// Write a map to a file, the format is chosen by the file extension
+/// An existing file is only replaced with `overwrite`
+pub fn create_file_from_map(pattern: &Pattern, file_path: &str, overwrite: bool) -> Result<(), PatternError> {
+    write_file(file_path, pattern.write(Format::from_path(file_path))?, overwrite)
+}
+
+/// Choose the rule of a map
+/// The rule given overrides the one in the file header, Conway's rule is used without both.
+/// The topology given overrides the one of the rule.
+/// A rule table is loaded here, from `RULES_FOLDER`, once the rule is chosen.
+fn choose_rule(rule: Option<Rule>, topology: Option<Topology>, file_rule: Option<Rule>) -> Result<Rule, PatternError> {
+    let rule = rule.or(file_rule).unwrap_or_default().load_table().map_err(PatternError::BadRule)?;
+
+    Ok(match topology {
+        Some(topology) => rule.with_topology(topology),
+        None => rule,
+    })
+}
+
+/// Choose the seed of a stochastic run
+/// The seed given overrides the one of the file, a new one is drawn from the clock without both.
+fn choose_seed(seed: Option<u64>, file_seed: Option<u64>) -> u64 {
+    seed.or(file_seed).unwrap_or_else(stochastic::time_seed)
+}
+
+/// Load a board from the content of a file, the format is detected from it
+fn create_map_from_text(text: &str, rule: Option<Rule>, topology: Option<Topology>) -> Result<Box<Board>, PatternError> {
+    let pattern = Pattern::read(text, Format::detect(text))?;
+
+    let board = Board::new(&pattern.cells, choose_rule(rule, topology, pattern.metadata.rule.clone())?);
+
+    Ok(Box::new(board.with_metadata(pattern.metadata)))
+}
+
+/// Can a rule run on HashLife and the tiles, a rule that isn't given by its neighbourhoods or is stochastic can't
+fn runs_on_any_engine(rule: &Rule) -> bool {
+    rule.uses_neighbourhoods() && rule.get_probabilities().is_none()
+}
 
-pub fn create_file_from_map(value: &String, _file_path: &str) {
-    fs::write("./map/saved_map.txt", value);
+/// Load a board from a file, the format is detected from its content
+/// The rule given overrides the one in the file header, Conway's rule is used without both.
+/// The topology given overrides the one of the rule.
+pub fn create_map_from_file(file_path: &str, rule: Option<Rule>, topology: Option<Topology>) -> Result<Box<Board>, PatternError> {
+    create_map_from_text(&fs::read_to_string(file_path)?, rule, topology)
 }
 
-pub fn create_map_from_file(file_path: &str) -> Box<Board> {
-    let lines = fs::read_to_string(file_path)
-        .expect("Something went wrong reading the file");
+/// Load a file in the chosen engine
+/// With HashLife, a macrocell file is loaded straight into the quadtree.
+fn create_engine_from_file(file_path: &str, options: &RunOptions, step_exponent: u32) -> Result<Box<dyn LifeEngine>, PatternError> {
+    let text = fs::read_to_string(file_path)?;
 
-    Box::new(Board::new(MAP_SIZE, lines.trim().lines().collect()))
+    if options.engine == EngineKind::HashLife && Format::detect(&text) == Format::Macrocell {
+        let macrocell = macrocell::parse(&text)?;
+        let rule = choose_rule(options.rule.clone(), options.topology, macrocell.metadata.rule.clone())?;
+        if *rule.get_topology() == Topology::Plane && rule.get_states() == 2 && runs_on_any_engine(&rule) {
+            let mut life = HashLife::from_macrocell(&macrocell, rule);
+            life.set_step_exponent(step_exponent);
+            life.set_history_depth(options.history);
+            return Ok(Box::new(life));
+        }
+    }
+    let board = create_map_from_text(&text, options.rule.clone(), options.topology)?;
+    let seed = choose_seed(options.seed, board.get_metadata().seed);
+    let mut engine = create_engine(Box::new(board.with_seed(seed)), options.engine, step_exponent);
+    engine.set_history_depth(options.history);
+    Ok(engine)
 }
 
 /// Run the ggez window
 /// Resizable got problems with osx
 /// The resizable is commit because it don't work on OSX and linux. It can be cause by ggez
-fn run_game(board: Box<Board>) {
+fn run_game(engine: Box<dyn LifeEngine>, save_path: Option<String>) {
     let mut c: conf::Conf = conf::Conf::new();
 
-    c.window_setup = c.window_setup.title(&"game of life");
+    c.window_setup = c.window_setup.title("game of life");
     c.window_mode = c.window_mode.resizable(true);
 
     let (mut ctx, event_loop) = ContextBuilder::new("game_of_life", "Erwan Bernard")
@@ -34,14 +155,147 @@ fn run_game(board: Box<Board>) {
         .build()
         .expect("aieee, could not create ggez context!");
 
-    let my_game = MyGame::new(&mut ctx, board);
+    let my_game = MyGame::new(&mut ctx, engine, save_path);
 
     // Run!
     event::run(ctx, event_loop, my_game)
 }
 
-pub fn load_run(mapfile: &str) {
-    let board = create_map_from_file(mapfile);
+/// Create the chosen engine from a loaded board
+/// With HashLife, each step of the game is 2^step_exponent generations.
+/// HashLife and the tiles only run two states rules on the unbounded plane, the board is used for the others.
+/// The tiles count the 8 neighbours, so they only run the outer-totalistic rules of the Moore neighbourhood.
+/// The Larger than Life rules, the rule tables, WireWorld and the stochastic rules run on the board, or the parallel board.
+fn create_engine(board: Box<Board>, engine: EngineKind, step_exponent: u32) -> Box<dyn LifeEngine> {
+    let neighbourhoods = runs_on_any_engine(board.get_rule());
+    let plane = *board.get_rule().get_topology() == Topology::Plane && board.get_rule().get_states() == 2 && neighbourhoods;
+    match engine {
+        EngineKind::HashLife | EngineKind::Tiles if !neighbourhoods => eprintln!("The rule {} runs on the board engine", board.get_rule()),
+        EngineKind::HashLife if !plane => eprintln!("HashLife only runs two states rules on the unbounded plane, the board engine is used"),
+        EngineKind::Tiles if !plane => eprintln!("The tiles only run two states rules on the unbounded plane, the board engine is used"),
+        EngineKind::Tiles if !board.get_rule().is_totalistic() => eprintln!("The tiles only run outer-totalistic rules of the Moore neighbourhood, the board engine is used"),
+        _ => {}
+    }
+    let hashlife = engine == EngineKind::HashLife;
+    if engine == EngineKind::Parallel {
+        Box::new(board.with_parallel(true))
+    } else if engine == EngineKind::Tiles && plane && board.get_rule().is_totalistic() {
+        let cells: Vec<_> = board.get_leaving_cells().iter().copied().collect();
+        Box::new(TileBoard::new(&cells, board.get_rule().clone()).with_metadata(board.get_metadata().clone()))
+    } else if hashlife && plane {
+        let life = HashLife::new(&board.get_leaving_cells().iter().copied().collect::<Vec<_>>(), board.get_rule().clone());
+        let mut life = life.with_metadata(board.get_metadata().clone());
+        life.set_step_exponent(step_exponent);
+        Box::new(life)
+    } else {
+        board
+    }
+}
+
+/// Load the map and run it with the chosen engine
+pub fn load_run(mapfile: &str, options: &RunOptions) -> Result<(), PatternError> {
+    let engine = create_engine_from_file(mapfile, options, options.step_exponent)?;
+
+    run_game(engine, options.save_path.clone());
+    Ok(())
+}
+
+/// Load the map and advance it without any window
+/// The result is written in the chosen format to the output file, or to stdout,
+/// with its metadata, and the population and bounding box as comments.
+/// The bounding box is in the board coordinates, the plaintext and MCell formats put its top left corner on (0, 0).
+/// An existing output file is only replaced with `overwrite`.
+pub fn load_run_headless(mapfile: &str, options: &RunOptions, generations: u64,
+                         format: Format, output: Option<&str>, overwrite: bool) -> Result<(), PatternError> {
+    let mut engine = create_engine_from_file(mapfile, options, 0)?;
+
+    engine.step_n(generations);
+
+    let mut pattern = Pattern::from_engine(&*engine);
+    // The statistics of a previous run are replaced
+    pattern.metadata.comments.retain(|comment| !comment.starts_with("population:") && !comment.starts_with("bounding box:"));
+    pattern.metadata.comments.push(format!("population: {}", engine.get_population()));
+    if let Some((s_x, s_y, b_x, b_y)) = engine.get_bounding_box() {
+        pattern.metadata.comments.push(format!("bounding box: ({}, {}) to ({}, {})", s_x, s_y, b_x, b_y));
+    }
+
+    let value = pattern.write(format)?;
+    match output {
+        Some(path) => write_file(path, value, overwrite)?,
+        None => print!("{}", value),
+    }
+    Ok(())
+}
+
+/// Load the map and print what kind of pattern it is: its period, speed, heat and volatility
+/// A period is looked for during `max_generations` generations, from the generation and with the seed a run would have.
+pub fn load_analyse(mapfile: &str, options: &RunOptions, max_generations: u64) -> Result<(), PatternError> {
+    let board = create_map_from_file(mapfile, options.rule.clone(), options.topology)?;
+    let seed = choose_seed(options.seed, board.get_metadata().seed);
+    let board = board.with_seed(seed);
+
+    println!("{}", analysis::analyse(&board, max_generations));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use board::cell::fixtures::sorted;
+
+    #[test]
+    fn seed_given_overrides_file_seed() {
+        assert_eq!(choose_seed(Some(1), Some(2)), 1);
+        assert_eq!(choose_seed(None, Some(2)), 2);
+    }
+
+    #[test]
+    fn analyse_and_run_start_alike() {
+        let text = "#C Seed: 42\n#CXRLE Gen=7\nx = 3, y = 1, rule = B3/S23/P0.5\n3o!\n";
+        let board = create_map_from_text(text, None, None).unwrap();
+        let seed = choose_seed(None, board.get_metadata().seed);
+        let copy = board.with_seed(seed).to_board();
+
+        assert_eq!((copy.get_generation(), copy.get_seed()), (7, Some(42)));
+    }
+
+    #[test]
+    fn days_to_dates() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+    }
+
+    #[test]
+    fn existing_file_is_only_replaced_with_overwrite() {
+        let path = std::env::temp_dir().join(format!("game-of-life-{}.txt", std::process::id()));
+        let path = path.to_str().unwrap();
+        write_file(path, String::from("first"), false).unwrap();
+
+        assert!(matches!(write_file(path, String::from("second"), false), Err(PatternError::FileExists(p)) if p == path));
+        assert_eq!(fs::read_to_string(path).unwrap(), "first");
+        write_file(path, String::from("third"), true).unwrap();
+        assert_eq!(fs::read_to_string(path).unwrap(), "third");
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn headless_run_writes_cells_and_statistics() {
+        let dir = std::env::temp_dir();
+        let input = dir.join(format!("game-of-life-blinker-{}.rle", std::process::id()));
+        let output = dir.join(format!("game-of-life-blinker-{}-out.rle", std::process::id()));
+        let (input, output) = (input.to_str().unwrap(), output.to_str().unwrap());
+        fs::write(input, "#N Blinker\nx = 3, y = 1, rule = B3/S23\n3o!\n").unwrap();
+
+        load_run_headless(input, &RunOptions::default(), 3, Format::Rle, Some(output), false).unwrap();
+        let pattern = Pattern::read(&fs::read_to_string(output).unwrap(), Format::Rle).unwrap();
+        fs::remove_file(input).unwrap();
+        fs::remove_file(output).unwrap();
 
-    run_game(board);
+        assert_eq!(sorted(&pattern.cells), vec![(1, -1), (1, 0), (1, 1)]);
+        assert_eq!(pattern.metadata.name.as_deref(), Some("Blinker"));
+        assert_eq!(pattern.metadata.generation, 3);
+        assert!(pattern.metadata.comments.contains(&String::from("population: 3")), "{:?}", pattern.metadata.comments);
+        assert!(pattern.metadata.comments.contains(&String::from("bounding box: (1, -1) to (1, 1)")), "{:?}", pattern.metadata.comments);
+    }
 }
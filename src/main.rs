@@ -1,5 +1,13 @@
-use clap::{Arg, App};
-use game_of_life::load_run;
+use std::process;
+use clap::{Arg, App, SubCommand};
+use game_of_life::{load_analyse, load_run, load_run_headless, RunOptions};
+use game_of_life::analysis::DEFAULT_MAX_GENERATIONS;
+use game_of_life::board::history::HistoryDepth;
+use game_of_life::board::rule::Rule;
+use game_of_life::board::topology::Topology;
+use game_of_life::engine::EngineKind;
+use game_of_life::hashlife::MAX_GENERATIONS;
+use game_of_life::pattern::Format;
 
 fn main() {
     let matches = App::new("Game of life")
@@ -11,10 +19,165 @@ fn main() {
             .takes_value(true)
             .help("config file for the board")
         )
+        .arg(Arg::with_name("rule")
+            .long("rule")
+            .short("r")
+            .takes_value(true)
+            .global(true)
+            .validator(|rule| rule.parse::<Rule>().map(|_| ()))
+            .help("rule of the board, like B3/S23, 23/3 or WireWorld, or the name of a .rule file in ./rules. Override the rule of the file")
+        )
+        .arg(Arg::with_name("topology")
+            .long("topology")
+            .short("t")
+            .takes_value(true)
+            .global(true)
+            .validator(|topology| topology.trim_start_matches(':').parse::<Topology>().map(|_| ()))
+            .help("shape of the board in the Golly notation, like :T100,80 for a torus. Override the topology of the rule")
+        )
+        .arg(Arg::with_name("seed")
+            .long("seed")
+            .takes_value(true)
+            .global(true)
+            .validator(|seed| seed.parse::<u64>().map(|_| ()).map_err(|e| e.to_string()))
+            .help("seed of the births and survivals of a stochastic rule, to replay a run. The seed of the file, or a new one, by default")
+        )
+        .arg(Arg::with_name("engine")
+            .long("engine")
+            .short("e")
+            .takes_value(true)
+            .global(true)
+            .possible_values(&["board", "parallel", "tiles", "hashlife"])
+            .default_value("board")
+            .help("simulation engine, parallel uses all the threads, tiles packs the cells in bits, hashlife can jump many generations at once")
+        )
+        .arg(Arg::with_name("step")
+            .long("step")
+            .short("s")
+            .takes_value(true)
+            .global(true)
+            .default_value("0")
+            .validator(|step| match step.parse::<u32>() {
+                Ok(step) if step < 32 => Ok(()),
+                _ => Err(String::from("the step must be a number lower than 32")),
+            })
+            .help("with hashlife, each step of the game is 2^step generations")
+        )
+        .arg(Arg::with_name("history")
+            .long("history")
+            .takes_value(true)
+            .global(true)
+            .default_value("10")
+            .validator(|depth| depth.parse::<HistoryDepth>().map(|_| ()))
+            .help("number of steps that can be undone, or unlimited")
+        )
+        .arg(Arg::with_name("save")
+            .long("save")
+            .takes_value(true)
+            .global(true)
+            .help("file the save buttons write to, the format is chosen by its extension. A timestamped file in ./map by default")
+        )
+        .subcommand(SubCommand::with_name("run")
+            .about("Advance a pattern without any window and write the result")
+            .arg(Arg::with_name("file")
+                .required(true)
+                .takes_value(true)
+                .help("config file for the board")
+            )
+            .arg(Arg::with_name("generations")
+                .long("generations")
+                .short("n")
+                .takes_value(true)
+                .default_value("0")
+                .validator(|n| n.parse::<u64>().map(|_| ()).map_err(|e| e.to_string()))
+                .help("number of generations to advance")
+            )
+            .arg(Arg::with_name("format")
+                .long("format")
+                .short("f")
+                .takes_value(true)
+                .possible_values(&["plaintext", "rle", "life105", "life106", "macrocell", "mcell"])
+                .help("format of the result, chosen by the output extension by default")
+            )
+            .arg(Arg::with_name("output")
+                .long("output")
+                .short("o")
+                .takes_value(true)
+                .help("file to write the result to, stdout by default")
+            )
+            .arg(Arg::with_name("force")
+                .long("force")
+                .help("replace the output file if it already exists")
+            )
+        )
+        .subcommand(SubCommand::with_name("analyse")
+            .about("Find the period, the speed, the heat and the volatility of a pattern")
+            .arg(Arg::with_name("file")
+                .required(true)
+                .takes_value(true)
+                .help("config file for the board")
+            )
+            .arg(Arg::with_name("generations")
+                .long("generations")
+                .short("n")
+                .takes_value(true)
+                .validator(|n| n.parse::<u64>().map(|_| ()).map_err(|e| e.to_string()))
+                .help("number of generations to look for a period in")
+            )
+        )
         .get_matches();
 
+    let options = RunOptions {
+        rule: matches.value_of("rule").map(|rule| rule.parse().unwrap()),
+        topology: matches.value_of("topology").map(|topology| topology.trim_start_matches(':').parse().unwrap()),
+        engine: matches.value_of("engine").unwrap().parse().unwrap(),
+        seed: matches.value_of("seed").map(|seed| seed.parse().unwrap()),
+        step_exponent: matches.value_of("step").unwrap().parse().unwrap(),
+        history: matches.value_of("history").unwrap().parse().unwrap(),
+        save_path: matches.value_of("save").map(String::from),
+    };
+
+    if let Some(run) = matches.subcommand_matches("run") {
+        let output = run.value_of("output");
+        let format = match run.value_of("format") {
+            Some("rle") => Format::Rle,
+            Some("life105") => Format::Life105,
+            Some("life106") => Format::Life106,
+            Some("macrocell") => Format::Macrocell,
+            Some("mcell") => Format::MCell,
+            Some(_) => Format::Plaintext,
+            None => output.map(Format::from_path).unwrap_or(Format::Plaintext),
+        };
+        let generations = run.value_of("generations").unwrap().parse().unwrap();
+        if options.engine == EngineKind::HashLife && generations > MAX_GENERATIONS {
+            eprintln!("HashLife can advance at most {} generations", MAX_GENERATIONS);
+            process::exit(1);
+        }
+
+        let overwrite = run.is_present("force");
+
+        if let Err(e) = load_run_headless(run.value_of("file").unwrap(), &options, generations, format, output, overwrite) {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    if let Some(analyse) = matches.subcommand_matches("analyse") {
+        let max_generations = analyse.value_of("generations").map_or(DEFAULT_MAX_GENERATIONS, |n| n.parse().unwrap());
+
+        if let Err(e) = load_analyse(analyse.value_of("file").unwrap(), &options, max_generations) {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
     let mapfile = matches.value_of("file").unwrap_or("input.txt");
     println!("The file passed is: {}", mapfile);
 
-    load_run(mapfile);
+    if let Err(e) = load_run(mapfile, &options) {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
 }
@@ -0,0 +1,52 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+/// What can go wrong while loading or saving a pattern
+/// <p> - Io: the file can't be read or written </p>
+/// <p> - UnknownChar: a character of the pattern has no meaning, with its line and column from 1 </p>
+/// <p> - BadLine: a line of the pattern can't be read, with its number from 1 </p>
+/// <p> - BadHeader: a header or rule line can't be read </p>
+/// <p> - BadRule: the rule table of the rule can't be loaded </p>
+/// <p> - TooLarge: the pattern doesn't fit on the board, or in the file format </p>
+/// <p> - FileExists: the save file is already there and should not be replaced without asking </p>
+#[derive(Debug)]
+pub enum PatternError {
+    Io(io::Error),
+    UnknownChar { c: char, line: usize, column: usize },
+    BadLine { line: usize, content: String },
+    BadHeader(String),
+    BadRule(String),
+    TooLarge(String),
+    FileExists(String),
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PatternError::Io(e) => write!(f, "I/O error: {}", e),
+            PatternError::UnknownChar { c, line, column } =>
+                write!(f, "Unknown character '{}' at line {}, column {}", c, line, column),
+            PatternError::BadLine { line, content } => write!(f, "Invalid line {}: '{}'", line, content),
+            PatternError::BadHeader(e) => write!(f, "Bad header: {}", e),
+            PatternError::BadRule(e) => write!(f, "Bad rule: {}", e),
+            PatternError::TooLarge(e) => write!(f, "Pattern too large: {}", e),
+            PatternError::FileExists(path) => write!(f, "The file {} already exists", path),
+        }
+    }
+}
+
+impl Error for PatternError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PatternError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for PatternError {
+    fn from(e: io::Error) -> Self {
+        PatternError::Io(e)
+    }
+}
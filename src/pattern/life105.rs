@@ -0,0 +1,125 @@
+use std::collections::HashSet;
+
+use crate::board::cell::{Cell, STATUS};
+use crate::board::rule::Rule;
+use super::plaintext::MAX_MAP_SIZE;
+use super::{Pattern, PatternError};
+
+/// First line of a Life 1.05 file
+const HEADER: &str = "#Life 1.05";
+
+/// Read a `#R 23/3` or `#N` rule line, None for another line
+pub(crate) fn rule_from_line(line: &str) -> Option<Result<Rule, PatternError>> {
+    match line.get(..2) {
+        Some("#N") => Some(Ok(Rule::conway())),
+        Some("#R") => Some(line[2..].parse().map_err(PatternError::BadHeader)),
+        _ => None,
+    }
+}
+
+/// Read a Life 1.05 pattern
+/// Each `#P x y` block is a grid of `.` and `*` with its top left corner on (x, y),
+/// a grid without block is put on (0, 0).
+/// The `#D` descriptions are kept in the metadata.
+pub fn read(text: &str) -> Result<Pattern, PatternError> {
+    let mut pattern = Pattern::default();
+    let (mut block_x, mut y) = (0i32, 0i32);
+
+    for (line_index, line) in text.lines().enumerate() {
+        let line = line.trim_end();
+        if let Some(position) = line.strip_prefix("#P") {
+            let mut values = position.split_whitespace().map(|value| value.parse::<i32>());
+            match (values.next(), values.next(), values.next()) {
+                (Some(Ok(x)), Some(Ok(block_y)), None) => { block_x = x; y = block_y; }
+                _ => return Err(PatternError::BadLine { line: line_index + 1, content: line.to_string() }),
+            }
+        } else if let Some(rule) = rule_from_line(line) {
+            pattern.metadata.rule = Some(rule?);
+        } else if let Some(description) = line.strip_prefix("#D") {
+            pattern.metadata.add_description(description);
+        } else if !line.starts_with('#') {
+            for (x, c) in line.chars().enumerate() {
+                match STATUS::get_from_char(c) {
+                    Some(status) if status.is_alive() => pattern.cells.push(Cell::new(block_x + x as i32, y, status)),
+                    Some(_) => {}
+                    None => return Err(PatternError::UnknownChar { c, line: line_index + 1, column: x + 1 }),
+                }
+            }
+            y += 1;
+        }
+    }
+    Ok(pattern)
+}
+
+/// Write a pattern in the Life 1.05 format, as one `#P` block
+/// The metadata is written as `#D` descriptions, the rule as `#N` for Conway's one or `#R 23/3`
+pub fn write(pattern: &Pattern) -> Result<String, PatternError> {
+    let mut res = format!("{}\n", HEADER);
+    pattern.metadata.get_descriptions().iter().for_each(|description| res += &format!("#D {}\n", description));
+    match pattern.metadata.rule.clone().unwrap_or_default() {
+        rule if rule == Rule::conway() => res += "#N\n",
+        rule => res += &format!("#R {}\n", rule.get_sb_notation()),
+    }
+
+    let (s_x, s_y, b_x, b_y) = match pattern.get_bounding_box() {
+        None => return Ok(res),
+        Some(bounding_box) => bounding_box
+    };
+    let (width, height) = (b_x as i64 - s_x as i64 + 1, b_y as i64 - s_y as i64 + 1);
+    if width * height > MAX_MAP_SIZE {
+        return Err(PatternError::TooLarge(format!("a {}x{} map is too big for the Life 1.05 format, use RLE", width, height)));
+    }
+    let cells: HashSet<(i32, i32)> = pattern.cells.iter().filter(|cell| cell.is_alive()).map(|cell| (cell.x, cell.y)).collect();
+
+    res += &format!("#P {} {}\n", s_x, s_y);
+    for y in s_y..(b_y + 1) {
+        let line: String = (s_x..(b_x + 1))
+            .map(|x| if cells.contains(&(x, y)) { '*' } else { '.' })
+            .collect();
+        match line.trim_end_matches('.') {
+            "" => res += ".\n",
+            line => res += &format!("{}\n", line),
+        }
+    }
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::cell::fixtures::{alive, sorted};
+
+    #[test]
+    fn read_blocks() {
+        let pattern = read("#Life 1.05\n#D Name: Two blocks\n#N\n#P -1 -1\n**\n**\n#P 4 0\n.*\n").unwrap();
+
+        assert_eq!(sorted(&pattern.cells), vec![(-1, -1), (-1, 0), (0, -1), (0, 0), (5, 0)]);
+        assert_eq!(pattern.metadata.name.as_deref(), Some("Two blocks"));
+        assert_eq!(pattern.metadata.rule, Some(Rule::conway()));
+    }
+
+    #[test]
+    fn round_trip() {
+        let cells = alive(&[(1, -3), (2, -2), (0, -1), (1, -1), (2, -1), (10, 4)]);
+        let mut pattern = Pattern::new(cells, Some("B36/S23".parse().unwrap()));
+        pattern.metadata.name = Some(String::from("Glider and cell"));
+        pattern.metadata.generation = 7;
+        let again = read(&write(&pattern).unwrap()).unwrap();
+
+        assert_eq!(again.metadata, pattern.metadata);
+        assert_eq!(sorted(&again.cells), sorted(&pattern.cells));
+    }
+
+    #[test]
+    fn unknown_char_position() {
+        match read("#Life 1.05\n#P 0 0\n.*.\n*x\n") {
+            Err(PatternError::UnknownChar { c, line, column }) => assert_eq!((c, line, column), ('x', 4, 2)),
+            other => panic!("expected an unknown character, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn bad_block_line() {
+        assert!(matches!(read("#Life 1.05\n#P 0\n*\n"), Err(PatternError::BadLine { line: 2, .. })));
+    }
+}
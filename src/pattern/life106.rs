@@ -0,0 +1,100 @@
+use std::collections::BTreeSet;
+
+use crate::board::cell::{Cell, STATUS};
+use super::life105::rule_from_line;
+use super::{Pattern, PatternError};
+
+/// First line of a Life 1.06 file
+const HEADER: &str = "#Life 1.06";
+
+/// Read a `x y` line of two coordinates
+fn read_coordinates(line: &str) -> Option<(i32, i32)> {
+    let mut values = line.split_whitespace().map(|value| value.parse::<i32>());
+
+    match (values.next(), values.next(), values.next()) {
+        (Some(Ok(x)), Some(Ok(y)), None) => Some((x, y)),
+        _ => None,
+    }
+}
+
+/// Is this line a `x y` line of two coordinates
+pub(crate) fn is_coordinates(line: &str) -> bool {
+    read_coordinates(line).is_some()
+}
+
+/// Read a Life 1.06 pattern, one `x y` line per living cell
+/// The coordinates are kept as they are.
+/// Like Life 1.05, the `#D` descriptions are kept in the metadata and a `#R` or `#N` line gives the rule.
+pub fn read(text: &str) -> Result<Pattern, PatternError> {
+    let mut pattern = Pattern::default();
+
+    for (line_index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if let Some(rule) = rule_from_line(line) {
+            pattern.metadata.rule = Some(rule?);
+            continue;
+        } else if let Some(description) = line.strip_prefix("#D") {
+            pattern.metadata.add_description(description);
+            continue;
+        } else if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match read_coordinates(line) {
+            Some((x, y)) => pattern.cells.push(Cell::new(x, y, STATUS::ALIVE)),
+            None => return Err(PatternError::BadLine { line: line_index + 1, content: line.to_string() }),
+        }
+    }
+    Ok(pattern)
+}
+
+/// Write a pattern in the Life 1.06 format, the cells are sorted by line
+/// The metadata is written as `#D` descriptions, and the rule in a `#R` line, Conway's one included so it is read back
+pub fn write(pattern: &Pattern) -> String {
+    let mut res = format!("{}\n", HEADER);
+    pattern.metadata.get_descriptions().iter().for_each(|description| res += &format!("#D {}\n", description));
+    if let Some(rule) = pattern.metadata.rule.as_ref() {
+        res += &format!("#R {}\n", rule.get_sb_notation());
+    }
+    let cells: BTreeSet<(i32, i32)> = pattern.cells.iter()
+        .filter(|cell| cell.is_alive())
+        .map(|cell| (cell.y, cell.x))
+        .collect();
+
+    cells.iter().fold(res, |acc, (y, x)| acc + &format!("{} {}\n", x, y))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::cell::fixtures::{alive, sorted};
+    use crate::board::rule::Rule;
+
+    #[test]
+    fn round_trip() {
+        let cells = alive(&[(1, -3), (2, -2), (0, -1), (1, -1), (2, -1), (-100, 250)]);
+        let mut pattern = Pattern::new(cells, Some("B3/S23/P0.5".parse().unwrap()));
+        pattern.metadata.author = Some(String::from("Someone"));
+        pattern.metadata.seed = Some(42);
+        let again = read(&write(&pattern)).unwrap();
+
+        assert_eq!(again.metadata, pattern.metadata);
+        assert_eq!(sorted(&again.cells), sorted(&pattern.cells));
+    }
+
+    #[test]
+    fn conway_rule_is_written() {
+        let pattern = Pattern::new(vec![Cell::new(0, 0, STATUS::ALIVE)], Some(Rule::conway()));
+
+        assert_eq!(write(&pattern), "#Life 1.06\n#R 23/3\n0 0\n");
+        assert_eq!(read(&write(&pattern)).unwrap().metadata.rule, Some(Rule::conway()));
+        assert_eq!(write(&Pattern::new(vec![], None)), "#Life 1.06\n");
+    }
+
+    #[test]
+    fn bad_coordinates_line() {
+        match read("#Life 1.06\n0 0\n1 x\n") {
+            Err(PatternError::BadLine { line, content }) => assert_eq!((line, content.as_str()), (3, "1 x")),
+            other => panic!("expected a bad line, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,279 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+use crate::board::cell::{Cell, STATUS};
+use super::{Pattern, PatternError, PatternMetadata};
+
+/// First line of a macrocell file
+const HEADER: &str = "[M2] (game-of-life)";
+
+/// Level of the 8x8 leaves
+pub const LEAF_LEVEL: u32 = 3;
+
+/// Above this level the positions don't fit in an i64
+const MAX_LEVEL: u32 = 62;
+
+/// A node of a macrocell quadtree
+/// <p> - Leaf: an 8x8 square, the bit `y * 8 + x` is set for a living cell </p>
+/// <p> - Node: a square of 2^level cells per side, with the index of its children nw, ne, sw, se.
+///  The index starts at 1, 0 is an empty child. </p>
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Hash)]
+pub enum MacroNode {
+    Leaf(u64),
+    Node { level: u32, children: [usize; 4] },
+}
+
+impl MacroNode {
+    pub fn get_level(&self) -> u32 {
+        match *self {
+            MacroNode::Leaf(_) => LEAF_LEVEL,
+            MacroNode::Node { level, .. } => level,
+        }
+    }
+}
+
+/// A pattern as stored in a macrocell file, the last node is the root
+/// The top left corner of the root is at (-2^(level - 1), -2^(level - 1)), like in Golly.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Macrocell {
+    pub nodes: Vec<MacroNode>,
+    pub metadata: PatternMetadata,
+}
+
+impl Macrocell {
+    /// Get the root of the quadtree, None for an empty pattern
+    pub fn get_root(&self) -> Option<&MacroNode> {
+        self.nodes.last()
+    }
+
+    /// Get the position of the top left corner of the root
+    pub fn get_origin(&self) -> (i64, i64) {
+        let half = self.get_root().map_or(0, |root| 1i64 << (root.get_level() - 1));
+
+        (-half, -half)
+    }
+
+    /// Get the node of an index, None for an empty child
+    pub fn get_node(&self, index: usize) -> Option<&MacroNode> {
+        index.checked_sub(1).map(|index| &self.nodes[index])
+    }
+}
+
+/// Read a `.**$*.*$` leaf line
+fn read_leaf(line: &str) -> Option<u64> {
+    let (mut x, mut y, mut bits) = (0u32, 0u32, 0u64);
+
+    for c in line.chars() {
+        match c {
+            '.' => x += 1,
+            '*' if x < 8 && y < 8 => { bits |= 1 << (y * 8 + x); x += 1; }
+            '$' => { x = 0; y += 1; }
+            _ => return None,
+        }
+    }
+    Some(bits)
+}
+
+/// Read a `level nw ne sw se` node line, the children must be already read
+fn read_node(line: &str, nodes: &[MacroNode]) -> Option<MacroNode> {
+    let values: Vec<usize> = line.split_whitespace().map(|value| value.parse().ok()).collect::<Option<_>>()?;
+    let (level, children) = match values.as_slice() {
+        &[level, nw, ne, sw, se] => (level as u32, [nw, ne, sw, se]),
+        _ => return None,
+    };
+    let is_child = |&index: &usize| index == 0
+        || nodes.get(index - 1).is_some_and(|child| child.get_level() + 1 == level);
+
+    if level > LEAF_LEVEL && level <= MAX_LEVEL && children.iter().all(is_child) {
+        Some(MacroNode::Node { level, children })
+    } else {
+        None
+    }
+}
+
+/// Read the quadtree of a two states macrocell file, with its `#R` rule and `#G` generation
+/// The `#N` name, `#O` author and `#C` comments are kept in the metadata.
+pub fn parse(text: &str) -> Result<Macrocell, PatternError> {
+    let mut macrocell = Macrocell::default();
+
+    for (line_index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        let bad_line = || PatternError::BadLine { line: line_index + 1, content: line.to_string() };
+        if line.is_empty() || line.starts_with('[') {
+            continue;
+        }
+        match line.get(..2) {
+            Some("#R") => macrocell.metadata.rule = Some(line[2..].parse().map_err(PatternError::BadHeader)?),
+            Some("#G") => macrocell.metadata.generation = line[2..].trim().parse().map_err(|_| bad_line())?,
+            Some("#N") => macrocell.metadata.name = Some(line[2..].trim().to_string()),
+            Some("#O") => macrocell.metadata.author = Some(line[2..].trim().to_string()),
+            Some("#C") => macrocell.metadata.add_comment(&line[2..]),
+            _ if line.starts_with('#') => {}
+            _ if line.starts_with(['.', '*', '$']) => macrocell.nodes.push(MacroNode::Leaf(read_leaf(line).ok_or_else(bad_line)?)),
+            _ => {
+                let node = read_node(line, &macrocell.nodes).ok_or_else(bad_line)?;
+                macrocell.nodes.push(node);
+            }
+        }
+    }
+    Ok(macrocell)
+}
+
+/// Add the living cells of a node to the list, the top left corner of the node is at (x, y)
+fn push_cells(macrocell: &Macrocell, index: usize, (x, y): (i64, i64), cells: &mut Vec<Cell>) -> Result<(), PatternError> {
+    match macrocell.get_node(index) {
+        None => Ok(()),
+        Some(&MacroNode::Leaf(bits)) => (0..64).filter(|i| bits & (1 << i) != 0).try_for_each(|i| {
+            let (cell_x, cell_y) = (i32::try_from(x + i % 8), i32::try_from(y + i / 8));
+            match (cell_x, cell_y) {
+                (Ok(cell_x), Ok(cell_y)) => { cells.push(Cell::new(cell_x, cell_y, STATUS::ALIVE)); Ok(()) }
+                _ => Err(PatternError::TooLarge(String::from("the macrocell pattern goes beyond the board coordinates"))),
+            }
+        }),
+        Some(&MacroNode::Node { level, children }) => {
+            let half = 1i64 << (level - 1);
+            push_cells(macrocell, children[0], (x, y), cells)?;
+            push_cells(macrocell, children[1], (x + half, y), cells)?;
+            push_cells(macrocell, children[2], (x, y + half), cells)?;
+            push_cells(macrocell, children[3], (x + half, y + half), cells)
+        }
+    }
+}
+
+/// Read a macrocell pattern into its living cells
+pub fn read(text: &str) -> Result<Pattern, PatternError> {
+    let macrocell = parse(text)?;
+    let mut cells = vec![];
+
+    push_cells(&macrocell, macrocell.nodes.len(), macrocell.get_origin(), &mut cells)?;
+    Ok(Pattern { cells, metadata: macrocell.metadata })
+}
+
+/// Build the quadtree of the cells inside the node at (x, y), the same nodes are written once
+fn build(cells: Vec<(i64, i64)>, (x, y): (i64, i64), level: u32, index: &mut HashMap<MacroNode, usize>, nodes: &mut Vec<MacroNode>) -> usize {
+    if cells.is_empty() {
+        return 0;
+    }
+    let node = if level == LEAF_LEVEL {
+        MacroNode::Leaf(cells.iter().fold(0, |bits, &(cell_x, cell_y)| bits | 1 << ((cell_y - y) * 8 + cell_x - x)))
+    } else {
+        let half = 1i64 << (level - 1);
+        let mut quadrants: [Vec<(i64, i64)>; 4] = Default::default();
+        for (cell_x, cell_y) in cells {
+            quadrants[(cell_x >= x + half) as usize + 2 * (cell_y >= y + half) as usize].push((cell_x, cell_y));
+        }
+        let positions = [(x, y), (x + half, y), (x, y + half), (x + half, y + half)];
+        let mut children = [0; 4];
+        for (i, quadrant) in quadrants.iter_mut().enumerate() {
+            children[i] = build(std::mem::take(quadrant), positions[i], level - 1, index, nodes);
+        }
+        MacroNode::Node { level, children }
+    };
+
+    *index.entry(node).or_insert_with(|| {
+        nodes.push(node);
+        nodes.len()
+    })
+}
+
+/// Write a pattern as a macrocell quadtree, with its metadata
+pub fn write(pattern: &Pattern) -> String {
+    let metadata = &pattern.metadata;
+    let mut res = format!("{}\n#R {}\n", HEADER, metadata.rule.clone().unwrap_or_default());
+    if metadata.generation > 0 {
+        res += &format!("#G {}\n", metadata.generation);
+    }
+    metadata.get_rle_comments().iter().for_each(|comment| res += &format!("{}\n", comment));
+
+    let cells: Vec<(i64, i64)> = pattern.cells.iter()
+        .filter(|cell| cell.is_alive())
+        .map(|cell| (cell.x as i64, cell.y as i64))
+        .collect();
+    // The root is centred on (0, 0), so it must be twice as large as the farthest cell
+    let farthest = cells.iter().map(|&(x, y)| x.max(-x - 1).max(y).max(-y - 1)).max().unwrap_or(0);
+    let level = (LEAF_LEVEL..).find(|&level| farthest < 1 << (level - 1)).unwrap();
+    let half = 1i64 << (level - 1);
+    let mut nodes = vec![];
+
+    build(cells, (-half, -half), level, &mut HashMap::new(), &mut nodes);
+    for node in nodes {
+        match node {
+            MacroNode::Leaf(bits) => {
+                let rows: Vec<String> = (0..8).map(|y| {
+                    let row: String = (0..8).map(|x| if bits & (1 << (y * 8 + x)) != 0 { '*' } else { '.' }).collect();
+                    row.trim_end_matches('.').to_string()
+                }).collect();
+                let used = rows.iter().rposition(|row| !row.is_empty()).map_or(0, |last| last + 1);
+                res += &rows[..used].iter().map(|row| format!("{}$", row)).collect::<String>();
+            }
+            MacroNode::Node { level, children: [nw, ne, sw, se] } => {
+                res += &format!("{} {} {} {} {}", level, nw, ne, sw, se);
+            }
+        }
+        res.push('\n');
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::cell::fixtures::{alive, sorted};
+
+    fn pattern(positions: &[(i32, i32)]) -> Pattern {
+        Pattern::new(alive(positions), Some("B36/S23".parse().unwrap()))
+    }
+
+    #[test]
+    fn read_glider() {
+        let pattern = read("[M2] (golly 4.2)\n#R B3/S23\n#G 12\n.*$..*$***$\n4 0 0 0 1\n").unwrap();
+
+        assert_eq!(sorted(&pattern.cells), vec![(0, 2), (1, 0), (1, 2), (2, 1), (2, 2)]);
+        assert_eq!(pattern.metadata.generation, 12);
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut pattern = pattern(&[(1, -3), (2, -2), (0, -1), (1, -1), (2, -1), (-1000, 70000), (-1, 0)]);
+        pattern.metadata.name = Some(String::from("Far apart"));
+        pattern.metadata.comments = vec![String::from("A glider and two cells")];
+        pattern.metadata.generation = 1 << 40;
+        let again = read(&write(&pattern)).unwrap();
+
+        assert_eq!(again.metadata, pattern.metadata);
+        assert_eq!(sorted(&again.cells), sorted(&pattern.cells));
+    }
+
+    #[test]
+    fn same_nodes_are_written_once() {
+        let blocks = pattern(&[(0, 0), (1, 0), (0, 1), (1, 1), (64, 0), (65, 0), (64, 1), (65, 1)]);
+        let macrocell = parse(&write(&blocks)).unwrap();
+        let leaves = macrocell.nodes.iter().filter(|node| matches!(node, MacroNode::Leaf(_))).count();
+
+        assert_eq!(leaves, 1);
+        assert_eq!(sorted(&read(&write(&blocks)).unwrap().cells), sorted(&blocks.cells));
+    }
+
+    #[test]
+    fn round_trip_empty() {
+        assert!(read(&write(&pattern(&[]))).unwrap().cells.is_empty());
+    }
+
+    #[test]
+    fn bad_child_index() {
+        match parse("[M2] (golly 4.2)\n*$\n4 1 0 0 2\n") {
+            Err(PatternError::BadLine { line, content }) => assert_eq!((line, content.as_str()), (3, "4 1 0 0 2")),
+            other => panic!("expected a bad line, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn bad_child_level() {
+        assert!(matches!(parse("[M2] (golly 4.2)\n*$\n5 1 0 0 0\n"), Err(PatternError::BadLine { line: 3, .. })));
+    }
+
+    #[test]
+    fn bad_leaf() {
+        assert!(matches!(parse("[M2] (golly 4.2)\n*x$\n"), Err(PatternError::BadLine { line: 2, .. })));
+    }
+}
@@ -0,0 +1,194 @@
+use std::collections::BTreeMap;
+
+use crate::board::cell::{Cell, STATUS};
+use crate::board::rule::Rule;
+use crate::board::wireworld;
+use super::{Pattern, PatternError};
+
+/// First line of an MCell file
+const HEADER: &str = "#MCell 4.20";
+
+/// The `#L` lines should not be longer than that
+const MAX_LINE_LENGTH: usize = 70;
+
+/// Get the MCell game of a rule, the family of rules it belongs to
+fn game(rule: &Rule) -> &'static str {
+    if rule.is_wireworld() {
+        "Special rules"
+    } else if rule.get_larger_than_life().is_some() {
+        "Larger than Life"
+    } else if rule.get_table_name().is_some() {
+        "Rules table"
+    } else if rule.get_states() > 2 {
+        "Generations"
+    } else {
+        "Life"
+    }
+}
+
+/// Get the letters of a state, `A` to `X` for 1 to 24, then `aA` to `jO`
+fn state_letters(state: u8) -> String {
+    let (prefix, letter) = ((state - 1) / 24, (b'A' + (state - 1) % 24) as char);
+
+    match prefix {
+        0 => letter.to_string(),
+        prefix => format!("{}{}", (b'a' + prefix - 1) as char, letter),
+    }
+}
+
+/// Read an MCell pattern
+/// The `#RULE` line gives the rule, a `#GAME` of WireWorld without it is WireWorld.
+/// The `#L` lines are the cells, with the top left corner on the (0, 0) position:
+/// `.` for an empty cell, `A` to `X` for the states 1 to 24 with an `a` to `j` prefix above, `$` for a new row,
+/// each one can follow a number of times. The `#D` descriptions are kept in the metadata.
+pub fn read(text: &str) -> Result<Pattern, PatternError> {
+    let mut pattern = Pattern::default();
+    let mut wireworld_game = false;
+    let too_large = || PatternError::TooLarge(String::from("the MCell pattern goes beyond the board coordinates"));
+    let (mut x, mut y, mut count) = (0i32, 0i32, 0i32);
+    // The `a` to `j` prefix of the states above 24
+    let mut prefix: Option<u32> = None;
+
+    for (line_index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        let (key, value) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        match key {
+            "#GAME" => wireworld_game = value.trim().eq_ignore_ascii_case(wireworld::NAME),
+            "#RULE" => pattern.metadata.rule = Some(value.parse().map_err(PatternError::BadHeader)?),
+            "#D" => pattern.metadata.add_description(value),
+            "#L" => {
+                let start = line.len() - value.len();
+                for (column, c) in value.chars().enumerate() {
+                    let run = count.max(1);
+                    let unknown = PatternError::UnknownChar { c, line: line_index + 1, column: start + column + 1 };
+                    match c {
+                        '0'..='9' => {
+                            count = count.checked_mul(10)
+                                .and_then(|count| count.checked_add(c.to_digit(10).unwrap() as i32))
+                                .ok_or_else(too_large)?;
+                            continue;
+                        }
+                        '.' => x = x.checked_add(run).ok_or_else(too_large)?,
+                        '$' => { x = 0; y = y.checked_add(run).ok_or_else(too_large)?; }
+                        'a'..='j' if prefix.is_none() => {
+                            prefix = Some(c as u32 - 'a' as u32 + 1);
+                            continue;
+                        }
+                        'A'..='X' => {
+                            let status = match prefix.take().unwrap_or(0) * 24 + c as u32 - 'A' as u32 + 1 {
+                                state if state <= u8::MAX as u32 => STATUS::from_state(state as u8),
+                                _ => return Err(unknown),
+                            };
+                            let end = x.checked_add(run).ok_or_else(too_large)?;
+                            pattern.cells.extend((x..end).map(|x| Cell::new(x, y, status)));
+                            x = end;
+                        }
+                        c if c.is_whitespace() => {}
+                        _ => return Err(unknown),
+                    }
+                    count = 0;
+                }
+            }
+            _ => {}
+        }
+    }
+    if pattern.metadata.rule.is_none() && wireworld_game {
+        pattern.metadata.rule = Some(Rule::wireworld());
+    }
+    Ok(pattern)
+}
+
+/// Write a pattern in the MCell format, with its game, its rule and its metadata as `#D` descriptions
+/// The cells are written with the states alphabet in `#L` lines, the top left corner of the pattern first.
+pub fn write(pattern: &Pattern) -> String {
+    let metadata = &pattern.metadata;
+    let rule = metadata.rule.clone().unwrap_or_default();
+    let mut res = format!("{}\n#GAME {}\n#RULE {}\n", HEADER, game(&rule), rule.get_sb_notation());
+    metadata.get_descriptions().iter().for_each(|description| res += &format!("#D {}\n", description));
+
+    let (s_x, s_y) = match pattern.get_bounding_box() {
+        None => return res,
+        Some((s_x, s_y, _, _)) => (s_x, s_y)
+    };
+    let mut rows: BTreeMap<i32, Vec<(i32, u8)>> = BTreeMap::new();
+    pattern.cells.iter().for_each(|cell| rows.entry(cell.y).or_default().push((cell.x, cell.status.get_state())));
+    let run = |count: i32, tag: &str| if count > 1 { format!("{}{}", count, tag) } else { tag.to_string() };
+
+    let mut runs = vec![];
+    let mut last_y = s_y;
+    for (y, mut row) in rows {
+        row.sort_unstable();
+        row.dedup_by_key(|&mut (x, _)| x);
+        if y != s_y {
+            runs.push(run(y - last_y, "$"));
+        }
+        last_y = y;
+
+        let mut x = s_x;
+        let mut cells = row.into_iter().peekable();
+        while let Some((start, state)) = cells.next() {
+            let mut end = start + 1;
+            while cells.next_if_eq(&(end, state)).is_some() {
+                end += 1;
+            }
+            if start > x {
+                runs.push(run(start - x, "."));
+            }
+            runs.push(run(end - start, &state_letters(state)));
+            x = end;
+        }
+    }
+
+    let mut line = String::new();
+    for run in runs {
+        if !line.is_empty() && line.len() + run.len() > MAX_LINE_LENGTH {
+            res += &format!("#L {}\n", line);
+            line.clear();
+        }
+        line += &run;
+    }
+    res + &format!("#L {}\n", line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::cell::fixtures::sorted_states;
+
+    #[test]
+    fn read_wireworld_game() {
+        let pattern = read("#MCell 4.20\n#GAME WireWorld\n#D A diode\n#L 2C.AB$\n#L C\n").unwrap();
+
+        assert_eq!(pattern.metadata.rule, Some(Rule::wireworld()));
+        assert_eq!(pattern.metadata.comments, vec![String::from("A diode")]);
+        assert_eq!(sorted_states(&pattern.cells), vec![(0, 0, 3), (1, 0, 3), (3, 0, 1), (4, 0, 2), (0, 1, 3)]);
+    }
+
+    #[test]
+    fn round_trip_wireworld() {
+        let pattern = read(include_str!("../../map/wireworld_clock.mcl")).unwrap();
+        let again = read(&write(&pattern)).unwrap();
+
+        assert_eq!(again.metadata, pattern.metadata);
+        assert_eq!(sorted_states(&again.cells), sorted_states(&pattern.cells));
+    }
+
+    #[test]
+    fn round_trip_many_states() {
+        let cells = (0..100).map(|x| Cell::new(x % 13, x / 13, STATUS::from_state((x % 60) as u8 + 1))).collect();
+        let mut pattern = Pattern::new(cells, Some("B2/S/C61".parse().unwrap()));
+        pattern.metadata.name = Some(String::from("States"));
+        let again = read(&write(&pattern)).unwrap();
+
+        assert_eq!(again.metadata, pattern.metadata);
+        assert_eq!(sorted_states(&again.cells), sorted_states(&pattern.cells));
+    }
+
+    #[test]
+    fn unknown_char_position() {
+        match read("#MCell 4.20\n#L 2A$\n#L .A?\n") {
+            Err(PatternError::UnknownChar { c, line, column }) => assert_eq!((c, line, column), ('?', 3, 6)),
+            other => panic!("expected an unknown character, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use crate::board::rule::Rule;
+
+/// What is known about a pattern, besides its cells
+/// <p> - name, author: given by the `!Name:`, `#N`, `!Author:`, `#O` lines </p>
+/// <p> - comments: the other comment lines </p>
+/// <p> - source_url: the first comment line that is a link, like the LifeWiki page of the pattern </p>
+/// <p> - rule: the rule given in the file, if any </p>
+/// <p> - generation: the generation the pattern was saved at </p>
+/// <p> - seed: the seed of a stochastic rule, given by a `Seed: 42` comment, to replay the run </p>
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct PatternMetadata {
+    pub name: Option<String>,
+    pub author: Option<String>,
+    pub comments: Vec<String>,
+    pub source_url: Option<String>,
+    pub rule: Option<Rule>,
+    pub generation: u64,
+    pub seed: Option<u64>,
+}
+
+/// Is this comment a link
+fn is_url(comment: &str) -> bool {
+    comment.starts_with("http://") || comment.starts_with("https://") || comment.starts_with("www.")
+}
+
+impl PatternMetadata {
+    /// Add a comment line, the first link is kept as the source url, and a `Seed:` line as the seed
+    pub fn add_comment(&mut self, comment: &str) {
+        let comment = comment.trim();
+
+        if let Some(seed) = comment.strip_prefix("Seed:").and_then(|seed| seed.trim().parse().ok()) {
+            self.seed = Some(seed);
+        } else if self.source_url.is_none() && is_url(comment) {
+            self.source_url = Some(comment.to_string());
+        } else {
+            self.comments.push(comment.to_string());
+        }
+    }
+
+    /// Add a description line of the formats without dedicated lines, like plaintext and Life 1.0x
+    /// The `Name:`, `Author:` and `Generation:` descriptions fill their fields, the others are comments.
+    pub fn add_description(&mut self, description: &str) {
+        let description = description.trim();
+
+        if let Some(name) = description.strip_prefix("Name:") {
+            self.name = Some(name.trim().to_string());
+        } else if let Some(author) = description.strip_prefix("Author:") {
+            self.author = Some(author.trim().to_string());
+        } else if let Some(generation) = description.strip_prefix("Generation:").and_then(|g| g.trim().parse().ok()) {
+            self.generation = generation;
+        } else {
+            self.add_comment(description);
+        }
+    }
+
+    /// Get the description lines of the formats without dedicated lines, read back by `add_description`
+    /// The rule is not in it, each format has its own rule line.
+    pub fn get_descriptions(&self) -> Vec<String> {
+        self.name.iter().map(|name| format!("Name: {}", name))
+            .chain(self.author.iter().map(|author| format!("Author: {}", author)))
+            .chain(Some(self.generation).filter(|&g| g > 0).map(|g| format!("Generation: {}", g)))
+            .chain(self.seed.map(|seed| format!("Seed: {}", seed)))
+            .chain(self.comments.iter().cloned())
+            .chain(self.source_url.iter().cloned())
+            .collect()
+    }
+
+    /// Get the comment lines of the RLE and macrocell formats, with the seed, without the generation and the rule
+    pub fn get_rle_comments(&self) -> Vec<String> {
+        self.name.iter().map(|name| format!("#N {}", name))
+            .chain(self.author.iter().map(|author| format!("#O {}", author)))
+            .chain(self.seed.map(|seed| format!("#C Seed: {}", seed)))
+            .chain(self.comments.iter().chain(self.source_url.iter()).map(|comment| format!("#C {}", comment)))
+            .collect()
+    }
+}
@@ -0,0 +1,192 @@
+pub mod error;
+pub mod life105;
+pub mod life106;
+pub mod macrocell;
+pub mod mcell;
+pub mod metadata;
+pub mod plaintext;
+pub mod rle;
+
+pub use error::PatternError;
+pub use metadata::PatternMetadata;
+
+use crate::board::cell::Cell;
+use crate::board::rule::Rule;
+use crate::board::cells_bounding_box;
+use crate::engine::LifeEngine;
+
+/// The file formats a pattern can be read from and written to
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum Format {
+    Plaintext,
+    Rle,
+    Life105,
+    Life106,
+    Macrocell,
+    MCell,
+}
+
+impl Format {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Plaintext => "cells",
+            Format::Rle => "rle",
+            Format::Life105 | Format::Life106 => "lif",
+            Format::Macrocell => "mc",
+            Format::MCell => "mcl",
+        }
+    }
+
+    /// Guess the format from the extension of a file, plaintext by default
+    /// The `.lif` files are written in Life 1.06
+    pub fn from_path(path: &str) -> Self {
+        let path = path.to_lowercase();
+
+        if path.ends_with(".rle") {
+            Format::Rle
+        } else if path.ends_with(".lif") || path.ends_with(".life") {
+            Format::Life106
+        } else if path.ends_with(".mc") {
+            Format::Macrocell
+        } else if path.ends_with(".mcl") {
+            Format::MCell
+        } else {
+            Format::Plaintext
+        }
+    }
+
+    /// Guess the format from the content of a file
+    /// <p> - Macrocell: a `[M2]` header </p>
+    /// <p> - MCell: a `#MCell` header </p>
+    /// <p> - Life105: a `#Life 1.05` header, or a `#P` block without RLE header </p>
+    /// <p> - Life106: a `#Life 1.06` header, or a first line of two coordinates </p>
+    /// <p> - Rle: a `x = .., y = ..` line after the `#` comments </p>
+    /// <p> - Plaintext: anything else, a `.cells`, `0`/`1` or `*`/`.` grid </p>
+    pub fn detect(text: &str) -> Self {
+        let mut lines = text.lines().map(str::trim).filter(|line| !line.is_empty()).peekable();
+
+        match lines.peek() {
+            Some(line) if line.starts_with("[M2]") => return Format::Macrocell,
+            Some(line) if line.starts_with("#MCell") => return Format::MCell,
+            Some(line) if line.starts_with("#Life 1.05") => return Format::Life105,
+            Some(line) if line.starts_with("#Life 1.06") => return Format::Life106,
+            _ => {}
+        }
+        // A `#P x y` line can also be an RLE comment, so the RLE header is looked for first
+        match lines.find(|line| !line.starts_with('#')) {
+            Some(line) if plaintext::is_header(line) => Format::Rle,
+            _ if text.lines().any(|line| line.starts_with("#P")) => Format::Life105,
+            Some(line) if life106::is_coordinates(line) => Format::Life106,
+            _ => Format::Plaintext,
+        }
+    }
+}
+
+/// A pattern as stored in a file
+/// <p> - cells: the living cells </p>
+/// <p> - metadata: its name, author, comments, rule and generation </p>
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Pattern {
+    pub cells: Vec<Cell>,
+    pub metadata: PatternMetadata,
+}
+
+impl Pattern {
+    pub fn new(cells: Vec<Cell>, rule: Option<Rule>) -> Self {
+        Pattern { cells, metadata: PatternMetadata { rule, ..PatternMetadata::default() } }
+    }
+
+    /// Get the living cells of an engine, with its metadata at its current generation and its seed
+    pub fn from_engine(engine: &dyn LifeEngine) -> Self {
+        let cells = match engine.get_bounding_box() {
+            None => vec![],
+            Some(rect) => engine.get_cells_in(rect)
+        };
+        let metadata = PatternMetadata {
+            rule: Some(engine.get_rule().clone()),
+            generation: engine.get_generation(),
+            seed: engine.get_seed(),
+            ..engine.get_metadata().clone()
+        };
+        Pattern { cells, metadata }
+    }
+
+    /// Get the living cells an engine was created with, with its metadata and its seed
+    pub fn from_initial_engine(engine: &dyn LifeEngine) -> Self {
+        let metadata = PatternMetadata { rule: Some(engine.get_rule().clone()), seed: engine.get_seed(), ..engine.get_metadata().clone() };
+
+        Pattern { cells: engine.get_initial_cells(), metadata }
+    }
+
+    pub fn read(text: &str, format: Format) -> Result<Self, PatternError> {
+        match format {
+            Format::Plaintext => plaintext::read(text),
+            Format::Rle => rle::read(text),
+            Format::Life105 => life105::read(text),
+            Format::Life106 => life106::read(text),
+            Format::Macrocell => macrocell::read(text),
+            Format::MCell => mcell::read(text),
+        }
+    }
+
+    /// Write the pattern in a format, only RLE and MCell keep the states above the living one
+    pub fn write(&self, format: Format) -> Result<String, PatternError> {
+        match format {
+            Format::Plaintext => plaintext::write(self),
+            Format::Rle => Ok(rle::write(self)),
+            Format::Life105 => life105::write(self),
+            Format::Life106 => Ok(life106::write(self)),
+            Format::Macrocell => Ok(macrocell::write(self)),
+            Format::MCell => Ok(mcell::write(self)),
+        }
+    }
+
+    /// Get the smallest rectangle containing the living cells, as (min_x, min_y, max_x, max_y)
+    pub fn get_bounding_box(&self) -> Option<(i32, i32, i32, i32)> {
+        cells_bounding_box(&self.cells)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::cell::fixtures::{alive, sorted};
+
+    #[test]
+    fn detect_formats() {
+        assert_eq!(Format::detect("[M2] (golly 4.2)\n#R B3/S23\n*$\n"), Format::Macrocell);
+        assert_eq!(Format::detect("#MCell 4.20\n#L A\n"), Format::MCell);
+        assert_eq!(Format::detect("#Life 1.05\n*\n"), Format::Life105);
+        assert_eq!(Format::detect("#D A block\n#P 0 0\n**\n**\n"), Format::Life105);
+        assert_eq!(Format::detect("#Life 1.06\n0 0\n"), Format::Life106);
+        assert_eq!(Format::detect("0 0\n1 0\n"), Format::Life106);
+        assert_eq!(Format::detect("#N Glider\nx = 3, y = 3\nbo$2bo$3o!\n"), Format::Rle);
+        assert_eq!(Format::detect("!Name: Block\nOO\nOO\n"), Format::Plaintext);
+    }
+
+    #[test]
+    fn rle_with_position_line() {
+        assert_eq!(Format::detect("#N Glider\n#P 10 20\nx = 3, y = 3, rule = B3/S23\nbo$2bo$3o!\n"), Format::Rle);
+    }
+
+    #[test]
+    fn metadata_round_trips() {
+        let metadata = PatternMetadata {
+            name: Some("Glider".to_string()),
+            author: Some("Richard K. Guy".to_string()),
+            comments: vec!["The smallest spaceship".to_string(), "Found in 1969".to_string()],
+            source_url: Some("https://conwaylife.com/wiki/Glider".to_string()),
+            rule: Some("B36/S23".parse().unwrap()),
+            generation: 12,
+            seed: Some(42),
+        };
+        let pattern = Pattern { cells: alive(&[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]), metadata };
+
+        for &format in &[Format::Plaintext, Format::Rle, Format::Life105, Format::Life106, Format::Macrocell, Format::MCell] {
+            let text = pattern.write(format).unwrap();
+            let read = Pattern::read(&text, format).unwrap();
+            assert_eq!(read.metadata, pattern.metadata, "{:?}:\n{}", format, text);
+            assert_eq!(sorted(&read.cells), sorted(&pattern.cells), "{:?}:\n{}", format, text);
+        }
+    }
+}
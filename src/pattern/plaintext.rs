@@ -0,0 +1,87 @@
+use std::collections::HashSet;
+use std::ops::Add;
+
+use crate::board::cell::{Cell, STATUS};
+use crate::board::rule::Rule;
+use super::{Pattern, PatternError, PatternMetadata};
+
+/// A map bigger than that, in number of characters, is not written
+pub(crate) const MAX_MAP_SIZE: i64 = 100_000_000;
+
+/// Is this line a `#` comment or a `x = 3, y = 3` size line
+pub(crate) fn is_header(line: &str) -> bool {
+    line.starts_with('#') || line.trim_start().strip_prefix('x')
+        .is_some_and(|rest| rest.trim_start().starts_with('='))
+}
+
+/// Read the rule given in the header of a pattern file
+/// It can be a `#R B3/S23` line or a `x = 3, y = 3, rule = B3/S23` line
+pub fn rule_from_header(lines: &[&str]) -> Result<Option<Rule>, PatternError> {
+    let rule = lines.iter().find_map(|line| {
+        if let Some(rule) = line.strip_prefix("#R") {
+            Some(rule)
+        } else if is_header(line) {
+            line.split(',')
+                .filter_map(|field| field.split_once('='))
+                .find(|(key, _)| key.trim() == "rule")
+                .map(|(_, rule)| rule)
+        } else {
+            None
+        }
+    });
+
+    rule.map(|rule| rule.parse().map_err(PatternError::BadHeader)).transpose()
+}
+
+/// Read a plaintext map, like a `.cells` one, the top left corner is put on the (0, 0) position
+pub fn read(text: &str) -> Result<Pattern, PatternError> {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut metadata = PatternMetadata { rule: rule_from_header(&lines)?, ..PatternMetadata::default() };
+    lines.iter()
+        .filter_map(|line| line.strip_prefix('!'))
+        .for_each(|description| metadata.add_description(description));
+
+    let mut cells = vec![];
+    let rows = lines.iter().enumerate()
+        .filter(|(_, line)| !line.starts_with('!') && !is_header(line))
+        .skip_while(|(_, line)| line.trim().is_empty());
+    for (y, (line_index, line)) in rows.enumerate() {
+        for (x, c) in line.trim_end().chars().enumerate() {
+            match STATUS::get_from_char(c) {
+                Some(status) if status.is_alive() => cells.push(Cell::new(x as i32, y as i32, status)),
+                Some(_) => {}
+                None => return Err(PatternError::UnknownChar { c, line: line_index + 1, column: x + 1 }),
+            }
+        }
+    }
+
+    Ok(Pattern { cells, metadata })
+}
+
+/// Write a pattern as a `.cells` map, the metadata is written in `!` lines
+/// The rule is written in a `#R` line when it is not Conway's one
+pub fn write(pattern: &Pattern) -> Result<String, PatternError> {
+    let header: String = pattern.metadata.get_descriptions().iter()
+        .map(|description| format!("!{}\n", description))
+        .chain(pattern.metadata.rule.as_ref().filter(|&rule| *rule != Rule::conway()).map(|rule| format!("#R {}\n", rule)))
+        .collect();
+    let (s_x, s_y, b_x, b_y) = match pattern.get_bounding_box() {
+        None => return Ok(header),
+        Some(bounding_box) => bounding_box
+    };
+    let (width, height) = (b_x as i64 - s_x as i64 + 1, b_y as i64 - s_y as i64 + 1);
+    if width * height > MAX_MAP_SIZE {
+        return Err(PatternError::TooLarge(format!("a {}x{} map is too big for the plaintext format, use RLE", width, height)));
+    }
+    let cells: HashSet<(i32, i32)> = pattern.cells.iter().filter(|cell| cell.is_alive()).map(|cell| (cell.x, cell.y)).collect();
+
+    Ok(header + &(s_y..(b_y + 1)).map(|y| {
+        (s_x..(b_x + 1)).map(|x| {
+            if cells.contains(&(x, y)) {
+                STATUS::ALIVE.get_char()
+            } else {
+                STATUS::DEAD.get_char()
+            }
+        }).collect::<String>().add("\n")
+    }).collect::<String>())
+}
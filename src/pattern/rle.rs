@@ -0,0 +1,273 @@
+use std::collections::BTreeMap;
+
+use crate::board::cell::{Cell, STATUS};
+use super::{Pattern, PatternError};
+
+/// The RLE lines should not be longer than that
+const MAX_LINE_LENGTH: usize = 70;
+
+/// Read the `x = 3, y = 3, rule = B3/S23` header line
+/// The rule is the last field, the rest of the line, a Larger than Life rule has commas.
+fn read_header(line: &str, pattern: &mut Pattern) -> Result<(), PatternError> {
+    let (line, rule) = match line.split_once("rule") {
+        Some((fields, rule)) => (fields.trim_end().trim_end_matches(','), Some(format!("rule{}", rule))),
+        None => (line, None),
+    };
+    for field in line.split(',').filter(|field| !field.trim().is_empty()).chain(rule.as_deref()) {
+        let (key, value) = field.split_once('=')
+            .ok_or_else(|| PatternError::BadHeader(format!("invalid RLE header field '{}'", field.trim())))?;
+
+        match key.trim() {
+            "x" | "y" => {
+                value.trim().parse::<u32>()
+                    .map_err(|_| PatternError::BadHeader(format!("invalid RLE size '{}'", value.trim())))?;
+            }
+            "rule" => pattern.metadata.rule = Some(value.parse().map_err(PatternError::BadHeader)?),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Read the generation of a `#CXRLE Pos=0,0 Gen=100` line
+fn read_xrle_generation(xrle: &str) -> Option<u64> {
+    xrle.split_whitespace()
+        .find_map(|field| field.strip_prefix("Gen="))
+        .and_then(|generation| generation.parse().ok())
+}
+
+/// Read the top left corner of a `#CXRLE Pos=-3,5 Gen=100` line
+fn read_xrle_position(xrle: &str) -> Option<(i32, i32)> {
+    let (x, y) = xrle.split_whitespace()
+        .find_map(|field| field.strip_prefix("Pos="))?
+        .split_once(',')?;
+    Some((x.trim().parse().ok()?, y.trim().parse().ok()?))
+}
+
+/// Get the letters of a state in the multi-state alphabet, `A` to `X` for 1 to 24, then `pA` to `yO`
+fn state_letters(state: u8) -> String {
+    let (prefix, letter) = ((state - 1) / 24, (b'A' + (state - 1) % 24) as char);
+
+    match prefix {
+        0 => letter.to_string(),
+        prefix => format!("{}{}", (b'p' + prefix - 1) as char, letter),
+    }
+}
+
+/// Read a Run Length Encoded pattern
+/// The top left corner of the `x`, `y` rectangle is put on the `Pos` of a `#CXRLE` line, or on the (0, 0) position.
+/// The `b`/`o` cells of the two states rules and the `.`/`A`/`pA` states of the multi-state alphabet are read.
+pub fn read(text: &str) -> Result<Pattern, PatternError> {
+    let mut pattern = Pattern::default();
+    let mut position = (0, 0);
+    let mut lines = text.lines().enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty())
+        .peekable();
+
+    while let Some((_, line)) = lines.next_if(|(_, line)| line.starts_with('#')) {
+        let metadata = &mut pattern.metadata;
+        match line.get(..2) {
+            Some("#R") | Some("#r") => metadata.rule = Some(line[2..].parse().map_err(PatternError::BadHeader)?),
+            Some("#N") => metadata.name = Some(line[2..].trim().to_string()),
+            Some("#O") => metadata.author = Some(line[2..].trim().to_string()),
+            Some("#C") | Some("#c") => match line[2..].trim().strip_prefix("XRLE") {
+                Some(xrle) => {
+                    metadata.generation = read_xrle_generation(xrle).unwrap_or(metadata.generation);
+                    position = read_xrle_position(xrle).unwrap_or(position);
+                }
+                None => metadata.add_comment(&line[2..]),
+            },
+            _ => {}
+        }
+    }
+    match lines.next_if(|(_, line)| line.starts_with('x')) {
+        Some((_, header)) => read_header(header, &mut pattern)?,
+        None => return Err(PatternError::BadHeader(String::from("missing RLE header 'x = .., y = ..'"))),
+    }
+
+    let too_large = || PatternError::TooLarge(String::from("the RLE pattern goes beyond the board coordinates"));
+    let (mut x, mut y, mut count) = (position.0, position.1, 0i32);
+    // The `p` to `y` prefix of the states above 24
+    let mut prefix: Option<u32> = None;
+    'data: for (line_index, line) in lines {
+        for (column, c) in line.chars().enumerate() {
+            let run = count.max(1);
+            match c {
+                '0'..='9' => {
+                    count = count.checked_mul(10)
+                        .and_then(|count| count.checked_add(c.to_digit(10).unwrap() as i32))
+                        .ok_or_else(too_large)?;
+                    continue;
+                }
+                'b' | '.' => x = x.checked_add(run).ok_or_else(too_large)?,
+                '$' => { x = position.0; y = y.checked_add(run).ok_or_else(too_large)?; }
+                '!' => break 'data,
+                'p'..='y' if prefix.is_none() => {
+                    prefix = Some(c as u32 - 'p' as u32 + 1);
+                    continue;
+                }
+                c if c.is_ascii_alphabetic() => {
+                    let unknown = PatternError::UnknownChar { c, line: line_index, column: column + 1 };
+                    let status = match (prefix.take(), c) {
+                        (prefix, 'A'..='X') => match prefix.unwrap_or(0) * 24 + c as u32 - 'A' as u32 + 1 {
+                            state if state <= u8::MAX as u32 => STATUS::from_state(state as u8),
+                            _ => return Err(unknown),
+                        },
+                        (None, _) => STATUS::ALIVE,
+                        (Some(_), _) => return Err(unknown),
+                    };
+                    let end = x.checked_add(run).ok_or_else(too_large)?;
+                    pattern.cells.extend((x..end).map(|x| Cell::new(x, y, status)));
+                    x = end;
+                }
+                c if c.is_whitespace() => {}
+                c => return Err(PatternError::UnknownChar { c, line: line_index, column: column + 1 }),
+            }
+            count = 0;
+        }
+    }
+    Ok(pattern)
+}
+
+/// Add a run to the encoded pattern, starting a new line if it gets too long
+fn push_run(res: &mut String, line_length: &mut usize, count: i32, tag: &str) {
+    let run = if count > 1 { format!("{}{}", count, tag) } else { tag.to_string() };
+
+    if *line_length + run.len() > MAX_LINE_LENGTH {
+        res.push('\n');
+        *line_length = 0;
+    }
+    *line_length += run.len();
+    res.push_str(&run);
+}
+
+/// Write a pattern as Run Length Encoded, with its metadata in `#` lines
+/// The top left corner and the generation are written in an extended `#CXRLE Pos=-3,5 Gen=100` line, like Golly does,
+/// when they are not (0, 0) and 0.
+/// The cells of a Generations rule are written with the multi-state alphabet.
+/// Only the living cells are walked through, so a sparse pattern is written quickly
+pub fn write(pattern: &Pattern) -> String {
+    let metadata = &pattern.metadata;
+    let mut res: String = metadata.get_rle_comments().iter().map(|comment| format!("{}\n", comment)).collect();
+    let bounding_box = pattern.get_bounding_box();
+    let mut xrle = vec![];
+    if let Some((s_x, s_y, _, _)) = bounding_box.filter(|&(s_x, s_y, _, _)| (s_x, s_y) != (0, 0)) {
+        xrle.push(format!("Pos={},{}", s_x, s_y));
+    }
+    if metadata.generation > 0 {
+        xrle.push(format!("Gen={}", metadata.generation));
+    }
+    if !xrle.is_empty() {
+        res += &format!("#CXRLE {}\n", xrle.join(" "));
+    }
+    let rule = metadata.rule.clone().unwrap_or_default();
+    let (s_x, s_y, b_x, b_y) = match bounding_box {
+        None => return res + &format!("x = 0, y = 0, rule = {}\n!\n", rule),
+        Some(bounding_box) => bounding_box
+    };
+    let mut rows: BTreeMap<i32, Vec<(i32, u8)>> = BTreeMap::new();
+    pattern.cells.iter().for_each(|cell| rows.entry(cell.y).or_default().push((cell.x, cell.status.get_state())));
+    let multi_state = rule.get_states() > 2;
+    let dead = if multi_state { "." } else { "b" };
+    let tag = |state: u8| if multi_state { state_letters(state) } else { String::from("o") };
+
+    res += &format!("x = {}, y = {}, rule = {}\n", b_x - s_x + 1, b_y - s_y + 1, rule);
+    let mut line_length = 0;
+    let mut last_y = s_y;
+    for (y, mut row) in rows {
+        row.sort_unstable();
+        row.dedup_by_key(|&mut (x, _)| x);
+        if y != s_y {
+            push_run(&mut res, &mut line_length, y - last_y, "$");
+        }
+        last_y = y;
+
+        let mut x = s_x;
+        let mut cells = row.into_iter().peekable();
+        while let Some((start, state)) = cells.next() {
+            let mut end = start + 1;
+            while cells.next_if_eq(&(end, state)).is_some() {
+                end += 1;
+            }
+            if start > x {
+                push_run(&mut res, &mut line_length, start - x, dead);
+            }
+            push_run(&mut res, &mut line_length, end - start, &tag(state));
+            x = end;
+        }
+    }
+    push_run(&mut res, &mut line_length, 1, "!");
+    res + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::cell::fixtures::sorted_states;
+
+    const GLIDER: &str = "#N Glider\n#O Richard K. Guy\n#C The smallest spaceship\nx = 3, y = 3, rule = B3/S23\nbob$2bo$3o!\n";
+
+    #[test]
+    fn read_glider() {
+        let pattern = read(GLIDER).unwrap();
+
+        assert_eq!(sorted_states(&pattern.cells), vec![(1, 0, 1), (2, 1, 1), (0, 2, 1), (1, 2, 1), (2, 2, 1)]);
+        assert_eq!(pattern.metadata.name.as_deref(), Some("Glider"));
+        assert_eq!(pattern.metadata.author.as_deref(), Some("Richard K. Guy"));
+        assert_eq!(pattern.metadata.rule, Some("B3/S23".parse().unwrap()));
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut pattern = read(GLIDER).unwrap();
+        pattern.metadata.generation = 42;
+        let again = read(&write(&pattern)).unwrap();
+
+        assert_eq!(again.metadata, pattern.metadata);
+        assert_eq!(sorted_states(&again.cells), sorted_states(&pattern.cells));
+    }
+
+    #[test]
+    fn round_trip_multi_state() {
+        let cells = vec![Cell::new(0, 0, STATUS::ALIVE), Cell::new(1, 0, STATUS::from_state(2)),
+                         Cell::new(5, 3, STATUS::from_state(30)), Cell::new(6, 3, STATUS::from_state(30))];
+        let pattern = Pattern::new(cells, Some("B2/S/C40".parse().unwrap()));
+        let text = write(&pattern);
+        let again = read(&text).unwrap();
+
+        assert!(text.contains("pF"), "{}", text);
+        assert_eq!(again.metadata.rule, pattern.metadata.rule);
+        assert_eq!(sorted_states(&again.cells), sorted_states(&pattern.cells));
+    }
+
+    #[test]
+    fn position_is_kept() {
+        let pattern = Pattern::new(vec![Cell::new(-3, 5, STATUS::ALIVE), Cell::new(-2, 6, STATUS::ALIVE)], None);
+        let text = write(&pattern);
+
+        assert!(text.starts_with("#CXRLE Pos=-3,5\n"), "{}", text);
+        assert_eq!(sorted_states(&read(&text).unwrap().cells), vec![(-3, 5, 1), (-2, 6, 1)]);
+        assert_eq!(sorted_states(&read("#CXRLE Pos=10,-1 Gen=3\nx = 2, y = 2\no$bo!\n").unwrap().cells), vec![(10, -1, 1), (11, 0, 1)]);
+    }
+
+    #[test]
+    fn round_trip_empty() {
+        let pattern = Pattern::new(vec![], None);
+
+        assert!(read(&write(&pattern)).unwrap().cells.is_empty());
+    }
+
+    #[test]
+    fn unknown_char_position() {
+        match read("#C comment\nx = 3, y = 2\nbo$\n2o?!\n") {
+            Err(PatternError::UnknownChar { c, line, column }) => assert_eq!((c, line, column), ('?', 4, 3)),
+            other => panic!("expected an unknown character, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn missing_header() {
+        assert!(matches!(read("bo$2bo$3o!"), Err(PatternError::BadHeader(_))));
+    }
+}
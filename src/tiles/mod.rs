@@ -0,0 +1,368 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::board::cell::{Cell, STATUS};
+use crate::board::history::HistoryDepth;
+use crate::board::rule::Rule;
+use crate::engine::LifeEngine;
+use crate::pattern::PatternMetadata;
+
+/// Number of cells on a side of a tile
+const TILE_SIZE: i32 = 64;
+
+/// A 64x64 square of cells, the bit x of the row y is set for a living cell
+type Tile = [u64; TILE_SIZE as usize];
+
+/// Position of a tile, the tile (tx, ty) starts at the cell (64 * tx, 64 * ty)
+type TilePos = (i32, i32);
+
+/// Get the tile of a cell and the position of the cell in it
+#[inline]
+fn split(x: i32, y: i32) -> (TilePos, usize, u32) {
+    ((x.div_euclid(TILE_SIZE), y.div_euclid(TILE_SIZE)), y.rem_euclid(TILE_SIZE) as usize, x.rem_euclid(TILE_SIZE) as u32)
+}
+
+/// Add a one bit number to every column of a four bits counter, a ripple carry adder on the 64 columns at once
+#[inline]
+fn add_bit(count: &mut [u64; 4], mut bit: u64) {
+    for digit in count.iter_mut() {
+        let carry = *digit & bit;
+        *digit ^= bit;
+        bit = carry;
+    }
+}
+
+/// Get the columns of a four bits counter equal to n
+#[inline]
+fn count_equals(count: &[u64; 4], n: usize) -> u64 {
+    count.iter().enumerate()
+        .fold(!0, |mask, (k, &digit)| mask & if n & (1 << k) != 0 { digit } else { !digit })
+}
+
+/// The board stored as 64x64 tiles of bits, only the tiles with living cells are kept.
+/// The neighbours of 64 cells are counted at once with bitwise adders,
+/// instead of hashing every neighbour of every cell.
+/// It only runs on the unbounded plane.
+pub struct TileBoard {
+    tiles: HashMap<TilePos, Tile>,
+    /// The changes of the tiles from a generation to the next, xor-ed, the last one is the newest
+    history: VecDeque<Vec<(TilePos, Tile)>>,
+    history_depth: HistoryDepth,
+    initial_state: HashMap<TilePos, Tile>,
+    generation: u64,
+    rule: Rule,
+    /// birth[n] and survival[n] as masks of 64 cells
+    birth: [u64; 9],
+    survival: [u64; 9],
+    /// A cell was set by hand, the initial cells don't lead to the current ones anymore
+    edited: bool,
+    metadata: PatternMetadata,
+}
+
+impl TileBoard {
+    /// Construct the board from its living cells
+    pub fn new(cells: &[Cell], rule: Rule) -> Self {
+        let mask = |alive: bool, n: usize| if rule.apply_count(alive, n) { !0 } else { 0 };
+        let mut board = TileBoard {
+            tiles: HashMap::new(),
+            history: VecDeque::new(),
+            history_depth: HistoryDepth::default(),
+            initial_state: HashMap::new(),
+            generation: 0,
+            rule: rule.clone(),
+            birth: [0; 9],
+            survival: [0; 9],
+            edited: false,
+            metadata: PatternMetadata::default(),
+        };
+
+        for n in 0..9 {
+            board.birth[n] = mask(false, n);
+            board.survival[n] = mask(true, n);
+        }
+        cells.iter().filter(|cell| cell.is_alive())
+            .for_each(|cell| { board.set_bit(cell.x, cell.y, true); });
+        board.initial_state = board.tiles.clone();
+        board
+    }
+
+    /// Attach the metadata of the loaded pattern, the board starts at its generation
+    pub fn with_metadata(self, metadata: PatternMetadata) -> Self {
+        TileBoard { generation: metadata.generation, metadata, ..self }
+    }
+
+    #[inline]
+    fn is_alive(&self, x: i32, y: i32) -> bool {
+        let (pos, row, column) = split(x, y);
+
+        self.tiles.get(&pos).is_some_and(|tile| tile[row] & (1 << column) != 0)
+    }
+
+    /// Set a cell, an empty tile is dropped. False when the cell was already in this state.
+    fn set_bit(&mut self, x: i32, y: i32, alive: bool) -> bool {
+        let (pos, row, column) = split(x, y);
+        let tile = self.tiles.entry(pos).or_insert([0; TILE_SIZE as usize]);
+        let was_alive = tile[row] & (1 << column) != 0;
+
+        if alive {
+            tile[row] |= 1 << column;
+        } else {
+            tile[row] &= !(1 << column);
+        }
+        if tile.iter().all(|&bits| bits == 0) {
+            self.tiles.remove(&pos);
+        }
+        was_alive != alive
+    }
+
+    /// Get the 66 rows of a column of tiles around a tile, with the last row of the tile above it
+    /// and the first row of the tile below it
+    fn column(&self, (tx, ty): TilePos) -> [u64; TILE_SIZE as usize + 2] {
+        let mut rows = [0; TILE_SIZE as usize + 2];
+
+        if let Some(tile) = self.tiles.get(&(tx, ty - 1)) {
+            rows[0] = tile[TILE_SIZE as usize - 1];
+        }
+        if let Some(tile) = self.tiles.get(&(tx, ty)) {
+            rows[1..=TILE_SIZE as usize].copy_from_slice(tile);
+        }
+        if let Some(tile) = self.tiles.get(&(tx, ty + 1)) {
+            rows[TILE_SIZE as usize + 1] = tile[0];
+        }
+        rows
+    }
+
+    /// Compute the next generation of a tile
+    fn next_tile(&self, (tx, ty): TilePos) -> Tile {
+        let west = self.column((tx - 1, ty));
+        let centre = self.column((tx, ty));
+        let east = self.column((tx + 1, ty));
+        // The bit x of left is the cell x - 1, the bit x of right is the cell x + 1
+        let left = |r: usize| (centre[r] << 1) | (west[r] >> 63);
+        let right = |r: usize| (centre[r] >> 1) | (east[r] << 63);
+        let mut tile = [0; TILE_SIZE as usize];
+
+        for (y, next) in tile.iter_mut().enumerate() {
+            let mut count = [0; 4];
+            for &bits in [left(y), centre[y], right(y), left(y + 1), right(y + 1), left(y + 2), centre[y + 2], right(y + 2)].iter() {
+                add_bit(&mut count, bits);
+            }
+            let alive = centre[y + 1];
+            *next = (0..9).fold(0, |res, n| {
+                res | (count_equals(&count, n) & ((alive & self.survival[n]) | (!alive & self.birth[n])))
+            });
+        }
+        tile
+    }
+
+    /// Apply the rule on the tiles with living cells and the tiles around them
+    pub fn next(&mut self) {
+        let interest: HashSet<TilePos> = self.tiles.keys()
+            .flat_map(|&(tx, ty)| (-1..=1).flat_map(move |dy| (-1..=1).map(move |dx| (tx + dx, ty + dy))))
+            .collect();
+        let tiles: HashMap<TilePos, Tile> = interest.into_iter()
+            .map(|pos| (pos, self.next_tile(pos)))
+            .filter(|(_, tile)| tile.iter().any(|&bits| bits != 0))
+            .collect();
+
+        let empty = [0; TILE_SIZE as usize];
+        let diff = self.tiles.keys().chain(tiles.keys().filter(|pos| !self.tiles.contains_key(pos)))
+            .map(|&pos| {
+                let (old, new) = (self.tiles.get(&pos).unwrap_or(&empty), tiles.get(&pos).unwrap_or(&empty));
+                let mut changes = [0; TILE_SIZE as usize];
+                changes.iter_mut().enumerate().for_each(|(y, bits)| *bits = old[y] ^ new[y]);
+                (pos, changes)
+            })
+            .filter(|(_, changes)| changes.iter().any(|&bits| bits != 0))
+            .collect();
+
+        self.history.push_back(diff);
+        self.trim_history();
+        self.tiles = tiles;
+        self.generation += 1;
+    }
+
+    fn trim_history(&mut self) {
+        while !self.history_depth.allows(self.history.len()) {
+            self.history.pop_front();
+        }
+    }
+
+    /// Undo the last generation, false when the history is empty
+    fn undo(&mut self) -> bool {
+        let diff = match self.history.pop_back() {
+            Some(diff) => diff,
+            None => return false,
+        };
+
+        for (pos, changes) in diff {
+            let tile = self.tiles.entry(pos).or_insert([0; TILE_SIZE as usize]);
+            tile.iter_mut().zip(changes.iter()).for_each(|(bits, change)| *bits ^= change);
+            if tile.iter().all(|&bits| bits == 0) {
+                self.tiles.remove(&pos);
+            }
+        }
+        self.generation -= 1;
+        true
+    }
+
+    /// Get the living cells of some tiles inside the rectangle (min_x, min_y, max_x, max_y)
+    fn cells_in(tiles: &HashMap<TilePos, Tile>, (min_x, min_y, max_x, max_y): (i32, i32, i32, i32)) -> Vec<Cell> {
+        let (min_tile, _, _) = split(min_x, min_y);
+        let (max_tile, _, _) = split(max_x, max_y);
+
+        tiles.iter()
+            .filter(|(&(tx, ty), _)| tx >= min_tile.0 && tx <= max_tile.0 && ty >= min_tile.1 && ty <= max_tile.1)
+            .flat_map(|(&(tx, ty), tile)| tile.iter().enumerate().flat_map(move |(y, &bits)| {
+                (0..TILE_SIZE).filter(move |&x| bits & (1 << x) != 0)
+                    .map(move |x| Cell::new(tx * TILE_SIZE + x, ty * TILE_SIZE + y as i32, STATUS::ALIVE))
+            }))
+            .filter(|cell| cell.x >= min_x && cell.x <= max_x && cell.y >= min_y && cell.y <= max_y)
+            .collect()
+    }
+}
+
+impl LifeEngine for TileBoard {
+    fn step(&mut self) {
+        self.next();
+    }
+
+    fn step_n(&mut self, n: u64) {
+        (0..n).for_each(|_| self.next());
+    }
+
+    fn prev(&mut self) {
+        self.undo();
+    }
+
+    /// Go to a generation, backward by undoing the changes or stepping again from the initial cells
+    /// The initial cells are not used once a cell was set by hand, the edits would be lost.
+    fn goto(&mut self, generation: u64) -> bool {
+        if generation < self.metadata.generation {
+            return false;
+        }
+        if generation < self.generation && self.generation - generation > self.history.len() as u64 {
+            if self.edited {
+                return false;
+            }
+            self.tiles = self.initial_state.clone();
+            self.generation = self.metadata.generation;
+            self.history.clear();
+        }
+        while self.generation > generation {
+            self.undo();
+        }
+        self.step_n(generation - self.generation);
+        true
+    }
+
+    fn set_history_depth(&mut self, depth: HistoryDepth) {
+        self.history_depth = depth;
+        self.trim_history();
+    }
+
+    fn get_cell(&self, x: i32, y: i32) -> Cell {
+        if self.is_alive(x, y) {
+            Cell::new(x, y, STATUS::ALIVE)
+        } else {
+            Cell::new(x, y, STATUS::DEAD)
+        }
+    }
+
+    /// Set a cell, the last change is updated so undoing it still gives back the previous generation
+    fn set_cell(&mut self, x: i32, y: i32, status: STATUS) -> Cell {
+        if self.set_bit(x, y, status.is_alive()) {
+            self.edited = true;
+            if let Some(diff) = self.history.back_mut() {
+                let (pos, row, column) = split(x, y);
+                match diff.iter_mut().find(|(p, _)| *p == pos) {
+                    Some((_, changes)) => changes[row] ^= 1 << column,
+                    None => {
+                        let mut changes = [0; TILE_SIZE as usize];
+                        changes[row] = 1 << column;
+                        diff.push((pos, changes));
+                    }
+                }
+            }
+        }
+        Cell::new(x, y, status)
+    }
+
+    fn get_cells_in(&self, rect: (i32, i32, i32, i32)) -> Vec<Cell> {
+        TileBoard::cells_in(&self.tiles, rect)
+    }
+
+    fn get_initial_cells(&self) -> Vec<Cell> {
+        TileBoard::cells_in(&self.initial_state, (i32::MIN, i32::MIN, i32::MAX, i32::MAX))
+    }
+
+    fn get_population(&self) -> u64 {
+        self.tiles.values().flat_map(|tile| tile.iter()).map(|bits| bits.count_ones() as u64).sum()
+    }
+
+    fn get_bounding_box(&self) -> Option<(i32, i32, i32, i32)> {
+        self.tiles.iter().map(|(&(tx, ty), tile)| {
+            let columns = tile.iter().fold(0, |columns, &bits| columns | bits);
+            let first_row = tile.iter().position(|&bits| bits != 0).unwrap_or(0) as i32;
+            let last_row = tile.iter().rposition(|&bits| bits != 0).unwrap_or(0) as i32;
+            let (x, y) = (tx * TILE_SIZE, ty * TILE_SIZE);
+
+            (x + columns.trailing_zeros() as i32, y + first_row, x + 63 - columns.leading_zeros() as i32, y + last_row)
+        }).reduce(|(s_x, s_y, b_x, b_y), (x0, y0, x1, y1)| (s_x.min(x0), s_y.min(y0), b_x.max(x1), b_y.max(y1)))
+    }
+
+    fn get_generation(&self) -> u64 {
+        self.generation
+    }
+
+    fn get_rule(&self) -> &Rule {
+        &self.rule
+    }
+
+    fn get_metadata(&self) -> &PatternMetadata {
+        &self.metadata
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+    use crate::board::cell::fixtures::{alive, positions};
+
+    /// A soup of cells around the origin, across the edges of the tiles
+    fn soup() -> Vec<Cell> {
+        let mut state: u32 = 12345;
+        let positions: Vec<(i32, i32)> = (-70..70).flat_map(|y| (-70..70).map(move |x| (x, y)))
+            .filter(|_| {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                (state >> 16) & 3 == 0
+            })
+            .collect();
+        alive(&positions)
+    }
+
+    /// Check that the tiles step like the board
+    fn check_against_board(rule: &str, generations: u64) {
+        let rule: Rule = rule.parse().unwrap();
+        let mut tiles = TileBoard::new(&soup(), rule.clone());
+        let mut board = Board::new(&soup(), rule);
+
+        for generation in 1..=generations {
+            tiles.step();
+            board.step();
+            assert_eq!(positions(&tiles), positions(&board), "generation {}", generation);
+        }
+        assert_eq!(tiles.get_population(), board.get_population());
+        assert_eq!(LifeEngine::get_bounding_box(&tiles), LifeEngine::get_bounding_box(&board));
+    }
+
+    #[test]
+    fn soup_matches_board() {
+        check_against_board("B3/S23", 30);
+    }
+
+    #[test]
+    fn highlife_soup_matches_board() {
+        check_against_board("B36/S23", 30);
+    }
+}